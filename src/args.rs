@@ -1,33 +1,220 @@
 use std::{
     env,
-    path::{Path, PathBuf},
+    path::{Component, Path, PathBuf},
+    time::Duration,
 };
 
-use crate::{exit_err, forge::TOML};
+use crate::{
+    exit_err,
+    forge::{
+        Compression, DEFAULT_COMPRESS_THRESHOLD, DEFAULT_MAX_FILES, DEFAULT_STAGE_TEMPLATE,
+        DebBuilder, FileType, Owner, PKG_NAME, PackageType, REQUIRED_DEB_FILES,
+        STAGE_TEMPLATE_PLACEHOLDERS, SearchDir, TOML,
+        color::{self, ColorChoice},
+        config::{self, Config},
+        error::{self, ErrorFormat},
+    },
+    warn,
+};
 
 const BUILD_DIR: &str = "build";
-const ARGS: [&str; 10] = [
+const ARGS: [&str; 89] = [
     "-h",
     "--help",
     "-b",
     "--binary-name",
     "-v",
+    "--pkg-version",
+    "--version-suffix",
+    "--version-from-changelog",
+    "-V",
     "--version",
     "-t",
     "--target",
     "-d",
     "--dry-run",
+    "--warnings-as-errors",
+    "--maintainer",
+    "--section",
+    "--depends",
+    "--depends-file",
+    "--pre-depends",
+    "--conflicts",
+    "--replaces",
+    "--provides",
+    "--distribution",
+    "--changelog-from-git",
+    "--changelog-conventional",
+    "--opt-layout",
+    "--print-path",
+    "--emit",
+    "--list-filetypes",
+    "--binary-sha256",
+    "--binary-sha256-file",
+    "--package",
+    "--prefer-dir",
+    "--strict",
+    "--orig-tarball",
+    "--exclude",
+    "--reproducible",
+    "--stage-template",
+    "--generate-copyright",
+    "-q",
+    "--quiet",
+    "--check-ownership",
+    "--allow-missing",
+    "--doc-link",
+    "--check",
+    "--tool-timeout",
+    "--incremental",
+    "--features",
+    "--dump-config",
+    "--list-found",
+    "--owner",
+    "--relative-symlinks",
+    "--build",
+    "--dpkg-deb",
+    "--deb-compression",
+    "--packages-index",
+    "--clean",
+    "--keep-staging",
+    "--search-dir",
+    "--debian-dir",
+    "--max-files",
+    "--init",
+    "--multi-arch",
+    "--essential",
+    "--dest-root",
+    "--compress-binary",
+    "--binary-copy",
+    "--locale-desktop",
+    "--changes",
+    "--verify-deb",
+    "--user-install",
+    "--shellcheck",
+    "--fix-installed-size",
+    "--color",
+    "--no-color",
+    "--error-format",
+    "--max-size",
+    "--compress-threshold",
+    "--only",
+    "--skip",
+    "--summary-json",
+    "--require",
+    "--package-type",
+    "--package-name",
+    "--fakeroot",
+    "--sbin",
+    "--wrapper",
+    "--relocate",
 ];
 
 pub struct Args {
     pub binary_name: Option<String>,
+    /// Name of the package when it is not derived from the binary name
+    pub package_name: Option<String>,
     pub version: Option<String>,
+    /// Appended to the resolved version, eg. `+build123` from CI
+    pub version_suffix: Option<String>,
+    /// Reads `$Version` from the first line of the changelog instead of Cargo.toml
+    pub version_from_changelog: bool,
     pub project_dir: PathBuf,
-    pub architecture: Architecture,
+    pub config: Config,
     pub dry_run: bool,
+    /// Runs every validator and exits with a consolidated report, without writing anything
+    pub check: bool,
+    pub warnings_as_errors: bool,
+    pub strict: bool,
+    /// `Some(group_conventional_commits)` when the changelog entry should be generated from git history
+    pub changelog_from_git: Option<bool>,
+    pub generate_copyright: bool,
+    pub opt_vendor: Option<String>,
+    /// Installs the binary to `usr/sbin` rather than `usr/local/bin`
+    pub sbin: bool,
+    /// Installs `wrapper.sh` as the command and moves the binary to `usr/lib/<name>/<name>`
+    pub wrapper: bool,
+    pub stage_template: Option<String>,
+    /// Plain install tree to stage into instead of a package tree, eg. for `DESTDIR` style consumers
+    pub dest_root: Option<PathBuf>,
+    pub print_path: Option<FileType>,
+    /// Prints a text file type as it would be staged, then exits
+    pub emit: Option<FileType>,
+    /// Moves a staged subtree from the first path to the second, both relative to the stage root, then exits
+    pub relocate: Option<(PathBuf, PathBuf)>,
+    pub binary_sha256: Option<String>,
+    /// Control files of each package to stage, relative to the project directory
+    pub packages: Vec<PathBuf>,
+    pub prefer_dir: Option<SearchDir>,
+    /// Additional project directories scanned recursively like `assets`
+    pub search_dirs: Vec<PathBuf>,
+    /// A debhelper style `debian/` directory whose files take precedence over any found by the default scan
+    pub debian_dir: Option<PathBuf>,
+    /// Limit on the entries scanned before the project path is assumed to be wrong
+    pub max_files: usize,
+    /// Size budget in bytes of each staged package, or of each `.deb` when building
+    pub max_size: Option<u64>,
+    /// Docs larger than this many bytes are staged gzip compressed
+    pub compress_threshold: u64,
+    pub orig_tarball: bool,
+    /// Glob patterns of project paths left out of the source tarball
+    pub excludes: Vec<String>,
+    pub reproducible: bool,
+    pub quiet: bool,
+    pub check_ownership: bool,
+    /// Runs `shellcheck` on the staged maintainer scripts
+    pub shellcheck: bool,
+    /// Corrects a stale `Installed-Size` in the staged control file instead of warning
+    pub fix_installed_size: bool,
+    pub color: ColorChoice,
+    pub error_format: ErrorFormat,
+    /// Required file types that only warn when missing
+    pub allow_missing: Vec<FileType>,
+    /// File types the primary package must ship on top of `REQUIRED_DEB_FILES`, including those of `package_type`
+    pub require: Vec<FileType>,
+    pub package_type: Option<PackageType>,
+    /// File types staged by themselves, leaving the package incomplete
+    pub only: Vec<FileType>,
+    /// File types left out of staging
+    pub skip: Vec<FileType>,
+    pub doc_link: bool,
+    /// Limit on how long a spawned external tool may run
+    pub tool_timeout: Option<Duration>,
+    pub incremental: bool,
+    /// Removes all of `build/tmp` before staging
+    pub clean: bool,
+    /// Never removes a previous staging, files are overwritten in place
+    pub keep_staging: bool,
+    /// Cargo features the binary was built with, used to pick between `[[bin]]` targets
+    pub features: Vec<String>,
+    pub dump_config: bool,
+    /// Prints the discovered files and their file types, then exits
+    pub list_found: bool,
+    /// Runs `upx` on the staged binary
+    pub compress_binary: bool,
+    /// Globs of provided files copied byte for byte instead of rendered as text
+    pub binary_copy: Vec<String>,
+    /// Merges `<name>.<locale>.desktop` translations into the desktop entry
+    pub locale_desktop: bool,
+    /// Creates skeleton debian files instead of staging
+    pub init: bool,
+    pub owners: Vec<Owner>,
+    pub relative_symlinks: bool,
+    /// How each staged package is built into a `.deb`, `None` to only stage
+    pub build: Option<DebBuilder>,
+    /// File the `Packages` index stanzas of built packages are written to, `-` for stdout
+    pub packages_index: Option<String>,
+    /// Where the JSON report of the run is written, `-` for stdout
+    pub summary_json: Option<String>,
+    /// Writes a `.changes` file describing the packages built for each architecture
+    pub changes: bool,
+    /// Inspects each built package with `dpkg-deb --info` and `--contents`
+    pub verify_deb: bool,
+    /// Copies templates from `debian/user` into the installing user's home directory from the postinst
+    pub user_install: bool,
 }
 
-#[derive(Default, Clone, Copy)]
+#[derive(Default, Clone, Copy, PartialEq, Eq, Hash, Debug)]
 pub(crate) enum Architecture {
     #[default]
     Amd64,
@@ -48,6 +235,29 @@ impl From<String> for Architecture {
 }
 
 impl Architecture {
+    /// Parses the Debian architecture name returned by `short`
+    pub(crate) fn from_short(str: &str) -> Option<Self> {
+        match str {
+            "amd64" => Some(Self::Amd64),
+            "arm64" => Some(Self::Arm64),
+            _ => None,
+        }
+    }
+
+    /// Architecture named by `DEB_HOST_ARCH`, set by Debian build tooling such as `dpkg-buildpackage`. An
+    /// unsupported value is ignored so the lower precedence targets apply
+    pub(crate) fn from_host_env() -> Option<Self> {
+        let host = std::env::var("DEB_HOST_ARCH").ok()?;
+        if host.is_empty() {
+            return None;
+        }
+        let architecture = Self::from_short(&host);
+        if architecture.is_none() {
+            warn!("ignored DEB_HOST_ARCH: '{host}', only amd64 and arm64 are supported")
+        }
+        architecture
+    }
+
     pub(crate) const fn target(self) -> &'static str {
         match self {
             Architecture::Amd64 => "x86_64-unknown-linux-gnu",
@@ -64,9 +274,179 @@ impl Architecture {
 }
 
 impl Args {
+    /// Every behavior flag and its value, for `--dump-config`
+    pub(crate) fn flags(&self) -> Vec<(&'static str, String)> {
+        let list = |items: &[String]| items.join(", ");
+        let optional = |value: Option<&str>| value.unwrap_or_default().to_string();
+
+        vec![
+            ("--version-suffix", optional(self.version_suffix.as_deref())),
+            (
+                "--version-from-changelog",
+                self.version_from_changelog.to_string(),
+            ),
+            ("--dry-run", self.dry_run.to_string()),
+            ("--check", self.check.to_string()),
+            ("--warnings-as-errors", self.warnings_as_errors.to_string()),
+            ("--strict", self.strict.to_string()),
+            (
+                "--changelog-from-git",
+                self.changelog_from_git.is_some().to_string(),
+            ),
+            (
+                "--changelog-conventional",
+                (self.changelog_from_git == Some(true)).to_string(),
+            ),
+            ("--generate-copyright", self.generate_copyright.to_string()),
+            ("--opt-layout", optional(self.opt_vendor.as_deref())),
+            ("--sbin", self.sbin.to_string()),
+            ("--wrapper", self.wrapper.to_string()),
+            ("--stage-template", optional(self.stage_template.as_deref())),
+            (
+                "--dest-root",
+                self.dest_root
+                    .as_ref()
+                    .map(|dest_root| dest_root.display().to_string())
+                    .unwrap_or_default(),
+            ),
+            ("--binary-sha256", optional(self.binary_sha256.as_deref())),
+            (
+                "--package",
+                self.packages
+                    .iter()
+                    .map(|package| package.display().to_string())
+                    .collect::<Vec<_>>()
+                    .join(", "),
+            ),
+            (
+                "--prefer-dir",
+                optional(self.prefer_dir.map(SearchDir::name)),
+            ),
+            (
+                "--search-dir",
+                self.search_dirs
+                    .iter()
+                    .map(|dir| dir.display().to_string())
+                    .collect::<Vec<_>>()
+                    .join(", "),
+            ),
+            (
+                "--debian-dir",
+                self.debian_dir
+                    .as_ref()
+                    .map(|dir| dir.display().to_string())
+                    .unwrap_or_default(),
+            ),
+            ("--max-files", self.max_files.to_string()),
+            (
+                "--max-size",
+                self.max_size
+                    .map(|bytes| bytes.to_string())
+                    .unwrap_or_default(),
+            ),
+            ("--compress-threshold", self.compress_threshold.to_string()),
+            ("--orig-tarball", self.orig_tarball.to_string()),
+            ("--exclude", list(&self.excludes)),
+            ("--reproducible", self.reproducible.to_string()),
+            ("--quiet", self.quiet.to_string()),
+            ("--check-ownership", self.check_ownership.to_string()),
+            ("--shellcheck", self.shellcheck.to_string()),
+            ("--fix-installed-size", self.fix_installed_size.to_string()),
+            ("--color", self.color.name().to_string()),
+            ("--error-format", self.error_format.name().to_string()),
+            (
+                "--allow-missing",
+                self.allow_missing
+                    .iter()
+                    .map(|file_type| format!("{file_type:?}"))
+                    .collect::<Vec<_>>()
+                    .join(", "),
+            ),
+            (
+                "--require",
+                self.require
+                    .iter()
+                    .map(|file_type| format!("{file_type:?}"))
+                    .collect::<Vec<_>>()
+                    .join(", "),
+            ),
+            (
+                "--package-type",
+                optional(self.package_type.map(PackageType::name)),
+            ),
+            (
+                "--only",
+                self.only
+                    .iter()
+                    .map(|file_type| format!("{file_type:?}"))
+                    .collect::<Vec<_>>()
+                    .join(", "),
+            ),
+            (
+                "--skip",
+                self.skip
+                    .iter()
+                    .map(|file_type| format!("{file_type:?}"))
+                    .collect::<Vec<_>>()
+                    .join(", "),
+            ),
+            ("--doc-link", self.doc_link.to_string()),
+            (
+                "--tool-timeout",
+                self.tool_timeout
+                    .map(|timeout| format!("{}s", timeout.as_secs()))
+                    .unwrap_or_default(),
+            ),
+            ("--incremental", self.incremental.to_string()),
+            ("--compress-binary", self.compress_binary.to_string()),
+            ("--binary-copy", list(&self.binary_copy)),
+            ("--locale-desktop", self.locale_desktop.to_string()),
+            ("--clean", self.clean.to_string()),
+            ("--keep-staging", self.keep_staging.to_string()),
+            ("--features", list(&self.features)),
+            ("--relative-symlinks", self.relative_symlinks.to_string()),
+            (
+                "--build",
+                self.build
+                    .map(|build| match build {
+                        DebBuilder::Native(_) => "native",
+                        DebBuilder::DpkgDeb(_, false) => "dpkg-deb",
+                        DebBuilder::DpkgDeb(_, true) => "fakeroot dpkg-deb",
+                    })
+                    .unwrap_or_default()
+                    .to_string(),
+            ),
+            (
+                "--deb-compression",
+                self.build
+                    .map(
+                        |(DebBuilder::Native(compression)
+                         | DebBuilder::DpkgDeb(compression, _))| {
+                            compression.name()
+                        },
+                    )
+                    .unwrap_or_default()
+                    .to_string(),
+            ),
+            ("--packages-index", optional(self.packages_index.as_deref())),
+            ("--summary-json", optional(self.summary_json.as_deref())),
+            ("--changes", self.changes.to_string()),
+            ("--verify-deb", self.verify_deb.to_string()),
+            ("--user-install", self.user_install.to_string()),
+            (
+                "--owner",
+                self.owners
+                    .iter()
+                    .map(|owner| format!("{}:{}:{}", owner.path, owner.user, owner.group))
+                    .collect::<Vec<_>>()
+                    .join(", "),
+            ),
+        ]
+    }
+
     fn ensure_unique(str: &str, from: &'static str) {
         if ARGS.contains(&str) {
-            exit_err!("{from} requires an input")
+            exit_err!(kind: "InvalidArgument"; "{from} requires an input")
         }
     }
 
@@ -77,7 +457,7 @@ impl Args {
 
     #[allow(clippy::ptr_arg)]
     fn validate_version(version: &String) {
-        Self::ensure_unique(version, "--version");
+        Self::ensure_unique(version, "--pkg-version");
         // Since Dpkg doesn't enforce semver like cargo does we will just trust the user has correctly formatted their
         // version string. See: https://manpages.ubuntu.com/manpages/xenial/man5/deb-version.5.html
     }
@@ -92,32 +472,166 @@ impl Args {
 
         let exist_err = |path: &Path| exit_err!("{} does not exist", path.display());
 
-        if path.is_absolute() {
-            if path.exists() {
-                return path;
-            }
+        let path = if path.is_absolute() {
+            path
+        } else {
+            env::current_dir()
+                .unwrap_or_else(|err| exit_err!("failed to read the current directory: {err}"))
+                .join(path)
+        };
+
+        if !path.exists() {
             exist_err(&path)
         }
+        path
+    }
 
-        let mut curr_dir = env::current_dir().unwrap();
-        curr_dir.push(path);
-
-        if !curr_dir.exists() {
-            exist_err(&curr_dir)
+    /// Whether `dir` holds a `Cargo.toml` or any of the directories that are searched for debian files
+    fn looks_like_project(dir: &Path) -> bool {
+        if dir.join(TOML).is_file() {
+            return true;
         }
-        curr_dir
+        std::fs::read_dir(dir).is_ok_and(|mut entries| {
+            entries.any(|entry| {
+                entry.is_ok_and(|entry| {
+                    entry.path().is_dir()
+                        && entry
+                            .file_name()
+                            .to_str()
+                            .and_then(SearchDir::from_name)
+                            .is_some()
+                })
+            })
+        })
     }
 
+    /// The current directory, or its parent when run from within the project's `build` directory. A filesystem root
+    /// has no file name and is used as is
     fn locate_valid_project_dir() -> PathBuf {
-        let curr_dir = env::current_dir().unwrap();
+        let curr_dir = env::current_dir()
+            .unwrap_or_else(|err| exit_err!("failed to read the current directory: {err}"));
 
-        if curr_dir.file_name().unwrap() == BUILD_DIR {
-            return curr_dir.parent().unwrap().to_owned();
+        if curr_dir
+            .file_name()
+            .is_some_and(|file_name| file_name == BUILD_DIR)
+        {
+            let Some(parent) = curr_dir.parent() else {
+                exit_err!(
+                    "'{}' has no parent directory, supply the project with --project-path",
+                    curr_dir.display()
+                )
+            };
+            return parent.to_owned();
         }
 
         curr_dir
     }
 
+    fn required_input(input: Option<String>, from: &'static str) -> String {
+        let Some(input) = input else {
+            exit_err!("{from} requires an input")
+        };
+        Self::ensure_unique(&input, from);
+        input
+    }
+
+    /// A `--relocate` path must name a directory within the stage root, outside of `DEBIAN`
+    fn validate_relocate(input: Option<String>) -> PathBuf {
+        let path = PathBuf::from(Self::required_input(input, "--relocate"));
+        if path.as_os_str().is_empty()
+            || !path
+                .components()
+                .all(|component| matches!(component, Component::Normal(_)))
+        {
+            exit_err!(
+                "--relocate paths must be relative to the stage root, found: '{}'",
+                path.display()
+            )
+        }
+        if path.starts_with("DEBIAN") {
+            exit_err!("--relocate can not move the DEBIAN directory")
+        }
+        path
+    }
+
+    /// Ensures the template stays within the project directory once its placeholders are filled in
+    fn validate_stage_template(template: String) -> String {
+        if let Some((unknown, _)) = template.match_indices('{').find(|&(i, _)| {
+            !STAGE_TEMPLATE_PLACEHOLDERS
+                .iter()
+                .any(|placeholder| template[i..].starts_with(placeholder))
+        }) {
+            exit_err!(
+                "unknown placeholder in --stage-template at: '{}', supported: {}",
+                &template[unknown..],
+                STAGE_TEMPLATE_PLACEHOLDERS.join(", ")
+            )
+        }
+
+        let mut stripped = template.clone();
+        for placeholder in STAGE_TEMPLATE_PLACEHOLDERS {
+            stripped = stripped.replace(placeholder, "x");
+        }
+
+        let path = Path::new(&stripped);
+        if stripped.trim().is_empty() || path.has_root() || path.is_absolute() {
+            exit_err!("--stage-template must be a relative path, found: '{template}'")
+        }
+        if path
+            .components()
+            .any(|component| !matches!(component, Component::Normal(_) | Component::CurDir))
+        {
+            exit_err!("--stage-template must not leave the project directory, found: '{template}'")
+        }
+        template
+    }
+
+    /// Parses `path:user:group`, the path must be absolute and the user and group valid account names or ids
+    fn parse_owner(input: String) -> Owner {
+        let mut parts = input.rsplitn(3, ':');
+        let (Some(group), Some(user), Some(path)) = (parts.next(), parts.next(), parts.next())
+        else {
+            exit_err!("--owner must be formatted as path:user:group, found: '{input}'")
+        };
+
+        if !path.starts_with('/') {
+            exit_err!("--owner path must be absolute, found: '{path}'")
+        }
+        let valid_name = |name: &str| {
+            name.starts_with(|c: char| c.is_ascii_lowercase() || c == '_')
+                && name
+                    .chars()
+                    .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || matches!(c, '_' | '-'))
+                || !name.is_empty() && name.chars().all(|c| c.is_ascii_digit())
+        };
+        for name in [user, group] {
+            if !valid_name(name) {
+                exit_err!("--owner has an invalid user or group name: '{name}'")
+            }
+        }
+
+        Owner {
+            path: path.to_string(),
+            user: user.to_string(),
+            group: group.to_string(),
+        }
+    }
+
+    fn validate_sha256(mut hash: String, from: &'static str) -> String {
+        if hash.len() != 64 || !hash.chars().all(|c| c.is_ascii_hexdigit()) {
+            exit_err!("{from} must be a 64 character hex encoded sha256, found: '{hash}'")
+        }
+        hash.make_ascii_lowercase();
+        hash
+    }
+
+    fn parse_file_type(input: Option<String>, from: &'static str) -> FileType {
+        let name = Self::required_input(input, from);
+        FileType::from_name(&name).unwrap_or_else(|| {
+            exit_err!("unknown file type: '{name}', see --list-filetypes for supported names")
+        })
+    }
+
     #[inline]
     fn exit_if(is_none: bool, err: &str) {
         if is_none {
@@ -127,30 +641,166 @@ impl Args {
 
     pub fn parse() -> Self {
         let (mut binary_name, mut target, mut version, mut project_dir) = (None, None, None, None);
-        let mut dry_run = false;
+        let (mut package_name, mut version_suffix) = (None, None);
+        let mut version_from_changelog = false;
+        let (mut opt_vendor, mut print_path, mut binary_sha256) = (None, None, None);
+        let mut emit = None;
+        let mut relocate = None;
+        let mut stage_template = None;
+        let mut config = Config::default();
+        let (mut packages, mut prefer_dir, mut search_dirs) = (Vec::new(), None, Vec::new());
+        let mut debian_dir = None;
+        let (mut orig_tarball, mut excludes, mut reproducible) = (false, Vec::new(), false);
+        let (mut dry_run, mut warnings_as_errors, mut strict) = (false, false, false);
+        let (mut quiet, mut check_ownership, mut shellcheck) = (false, false, false);
+        let mut fix_installed_size = false;
+        let (mut color, mut error_format) = (ColorChoice::default(), ErrorFormat::default());
+        let (mut allow_missing, mut doc_link, mut check) = (Vec::new(), false, false);
+        let (mut only, mut skip) = (Vec::new(), Vec::new());
+        let (mut require, mut package_type) = (Vec::new(), None);
+        let (mut tool_timeout, mut incremental, mut features) = (None, false, Vec::new());
+        let (mut dump_config, mut owners, mut relative_symlinks) = (false, Vec::new(), false);
+        let mut list_found = false;
+        let (mut changelog_from_git, mut changelog_conventional) = (false, false);
+        let mut generate_copyright = false;
+        let (mut build, mut dpkg_deb, mut deb_compression) = (false, false, None);
+        let (mut fakeroot, mut sbin, mut wrapper) = (false, false, false);
+        let (mut packages_index, mut changes, mut summary_json) = (None, false, None);
+        let (mut verify_deb, mut user_install) = (false, false);
+        let (mut clean, mut keep_staging) = (false, false);
+        let (mut max_files, mut max_size) = (DEFAULT_MAX_FILES, None);
+        let mut compress_threshold = DEFAULT_COMPRESS_THRESHOLD;
+        let (mut init, mut dest_root, mut compress_binary) = (false, None, false);
+        let (mut binary_copy, mut locale_desktop) = (Vec::new(), false);
 
-        let mut args = std::env::args().skip(1);
+        let mut args = std::env::args().skip(1).peekable();
         while let Some(arg) = args.next() {
             match arg.as_str() {
                 "-h" | "--help" => {
                     println!(
                         "debforge v{} - Usage:\n    \
                         [-b binary-name](optional | default: will attempt to parse {TOML})\n    \
-                        [-v version](optional | default: will attempt to parse {TOML})\n    \
+                        [--package-name name](optional | default: the binary name with '_' replaced by '-')\n    \
+                        [--pkg-version version](optional | default: will attempt to parse {TOML}, -v is deprecated)\n    \
+                        [--version-suffix suffix](optional | appended to the version, eg. +build123 or ~git.abc123)\n    \
+                        [--version-from-changelog](optional | reads the version from the changelog's first line instead)\n    \
+                        [-V --version](optional | prints the version of debforge)\n    \
+                        [--features list](optional, repeatable | selects the [[bin]] whose required-features are enabled)\n    \
                         [-t target](optional | default: x86_64-unknown-linux-gnu)\n    \
                         [-p project-path](optional | default: current directory)\n    \
-                        [-d dry-run](optional | will display all found relevant deb files)",
+                        [--maintainer name](optional | default: [package.metadata.debforge] maintainer)\n    \
+                        [--section section](optional | default: [package.metadata.debforge] section)\n    \
+                        [--depends package](optional, repeatable | default: [package.metadata.debforge] depends)\n    \
+                        [--depends-file path](optional | merges 1 dependency per line into Depends, # starts a comment)\n    \
+                        [--pre-depends package](optional, repeatable | default: [package.metadata.debforge] pre-depends)\n    \
+                        [--conflicts package](optional, repeatable | default: [package.metadata.debforge] conflicts)\n    \
+                        [--replaces package](optional, repeatable | default: [package.metadata.debforge] replaces)\n    \
+                        [--provides package](optional, repeatable | default: [package.metadata.debforge] provides)\n    \
+                        [--multi-arch same|foreign|allowed|no](optional | sets the control file's Multi-Arch field)\n    \
+                        [--essential](optional | marks the package Essential: yes)\n    \
+                        [--distribution name](optional | default: unstable)\n    \
+                        [--changelog-from-git](optional | adds a changelog entry from commits since the last tag)\n    \
+                        [--changelog-conventional](optional | groups generated changelog entries by commit type)\n    \
+                        [--generate-copyright](optional | writes a copyright file from {TOML} when none is found)\n    \
+                        [--opt-layout vendor](optional | installs the binary and docs under /opt/vendor/name)\n    \
+                        [--sbin](optional | installs the binary to usr/sbin, eg. for a daemon)\n    \
+                        [--wrapper](optional | installs wrapper.sh as the command, the binary moves to usr/lib/name)\n    \
+                        [--doc-link](optional | links usr/share/doc/package to the control file's Source package)\n    \
+                        [--print-path filetype](optional | prints where a file type will be staged)\n    \
+                        [--emit filetype](optional | prints a text file type as it would be staged, eg. control)\n    \
+                        [--relocate old new](optional | moves a staged subtree, eg. usr/local to usr, then exits)\n    \
+                        [--init](optional | creates skeleton debian files and an example .desktop entry)\n    \
+                        [--list-filetypes](optional | lists all supported file types)\n    \
+                        [--compress-binary](optional | compresses the staged binary with upx when it is installed)\n    \
+                        [--binary-copy glob](optional, repeatable | copies matching files as is, without placeholders)\n    \
+                        [--locale-desktop](optional | merges <name>.<locale>.desktop translations into the desktop entry)\n    \
+                        [--binary-sha256 hex](optional | verifies the binary before staging)\n    \
+                        [--binary-sha256-file path](optional | reads the expected binary sha256 from a file)\n    \
+                        [--package control-file](optional, repeatable | stages a separate package per control file)\n    \
+                        [--prefer-dir assets|build|debian](optional | picks which directory's file wins duplicates)\n    \
+                        [--search-dir path](optional, repeatable | also scans this project directory like assets)\n    \
+                        [--debian-dir path](optional | a debhelper style debian directory whose files win over the default scan)\n    \
+                        [--max-files count](optional | default: {DEFAULT_MAX_FILES}, entries scanned before giving up)\n    \
+                        [--max-size bytes](optional | fails when a staged package, or a built .deb, is larger)\n    \
+                        [--compress-threshold bytes](optional | default: {DEFAULT_COMPRESS_THRESHOLD}, larger docs other than changelogs are staged gzip compressed)\n    \
+                        [--strict](optional | validation problems are errors instead of warnings)\n    \
+                        [--orig-tarball](optional | writes a {{source}}_{{version}}.orig.tar.gz of the project)\n    \
+                        [--exclude glob](optional, repeatable | leaves matching paths out of the source tarball)\n    \
+                        [--reproducible](optional | produces byte for byte identical output for identical input)\n    \
+                        [--stage-template template](optional | default: {DEFAULT_STAGE_TEMPLATE})\n    \
+                        [--dest-root path](optional | stages only installed files into a plain tree, without DEBIAN)\n    \
+                        [--allow-missing filetype](optional, repeatable | a missing required file only warns)\n    \
+                        [--require filetype](optional, repeatable | the primary package must also ship this file type)\n    \
+                        [--package-type cli|gui|library](optional | requires a Desktop file for gui, Symbols for library)\n    \
+                        [--only filetype](optional, repeatable | restages only the given file types)\n    \
+                        [--skip filetype](optional, repeatable | leaves the given file types out of staging)\n    \
+                        [--check-ownership](optional | warns when dpkg reports a staged path is owned by another package)\n    \
+                        [--shellcheck](optional | runs shellcheck on the staged maintainer scripts when it is installed)\n    \
+                        [--fix-installed-size](optional | corrects a stale Installed-Size in the staged control file)\n    \
+                        [--color auto|always|never](optional | default: auto, colors warnings, errors and success)\n    \
+                        [--no-color](optional | same as --color never)\n    \
+                        [--error-format text|json](optional | default: text, json prints fatal errors and warnings as JSON objects)\n    \
+                        [--check](optional | runs all validators, exits with an error if any fail)\n    \
+                        [--tool-timeout secs](optional | kills external tools, eg. git, that run longer)\n    \
+                        [--incremental](optional | only restages files whose source or variables changed)\n    \
+                        [--clean](optional | removes all of build/tmp before staging)\n    \
+                        [--keep-staging](optional | never removes a previous staging, files are overwritten)\n    \
+                        [--owner path:user:group](optional, repeatable | chowns an installed path in postinst)\n    \
+                        [--relative-symlinks](optional | links within 1 top-level directory use relative targets)\n    \
+                        [--build](optional | builds a .deb from each staged package)\n    \
+                        [--dpkg-deb](optional | builds with dpkg-deb instead of the built-in writer, implies --build)\n    \
+                        [--fakeroot](optional | runs dpkg-deb under fakeroot instead of passing --root-owner-group)\n    \
+                        [--deb-compression gz|xz|none](optional | default: gz)\n    \
+                        [--packages-index path|-](optional | writes an apt Packages index of the built packages)\n    \
+                        [--summary-json path|-](optional | writes a JSON report of what the run staged and built)\n    \
+                        [--changes](optional | writes a .changes file of the built packages for uploading)\n    \
+                        [--verify-deb](optional | prints dpkg-deb --info and --contents of each built package and checks them)\n    \
+                        [--user-install](optional | postinst copies debian/user templates into the installing user's home)\n    \
+                        [--dump-config](optional | prints every effective setting and where it came from)\n    \
+                        [--list-found](optional | prints each discovered file and its file type, without validating)\n    \
+                        [-d dry-run](optional | will display all found relevant deb files and the commands that would run)\n    \
+                        [-q quiet](optional | only prints warnings and errors)\n    \
+                        [--warnings-as-errors](optional | dry run exits with an error if any warnings were emitted)",
                         env!("CARGO_PKG_VERSION")
                     );
-                    std::process::exit(0);
+                    crate::forge::warnings::exit(0);
                 }
                 "-b" | "--binary-name" => {
                     binary_name = args.next().inspect(Self::validate_name);
                     Self::exit_if(binary_name.is_none(), "--binary-name requires an input")
                 }
-                "-v" | "--version" => {
+                "--package-name" => {
+                    package_name = Some(Self::required_input(args.next(), "--package-name"))
+                }
+                "-V" | "--version" => {
+                    // `--version` used to set the package version, don't let an old invocation silently do nothing
+                    if args.peek().is_some_and(|next| !next.starts_with('-')) {
+                        exit_err!(
+                            "--version prints {PKG_NAME}'s version, use --pkg-version to set the package version"
+                        )
+                    }
+                    println!("{PKG_NAME} v{}", env!("CARGO_PKG_VERSION"));
+                    crate::forge::warnings::exit(0);
+                }
+                "--version-suffix" => {
+                    let suffix = Self::required_input(args.next(), "--version-suffix");
+                    if !suffix
+                        .chars()
+                        .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '+' | '~' | '-'))
+                    {
+                        exit_err!(
+                            "--version-suffix may only contain alphanumerics and '.', '+', '~' or '-', found: '{suffix}'"
+                        )
+                    }
+                    version_suffix = Some(suffix)
+                }
+                "--version-from-changelog" => version_from_changelog = true,
+                "-v" | "--pkg-version" => {
+                    if arg == "-v" {
+                        warn!("-v is deprecated, use --pkg-version")
+                    }
                     version = args.next().inspect(Self::validate_version);
-                    Self::exit_if(version.is_none(), "--version requires an input")
+                    Self::exit_if(version.is_none(), "--pkg-version requires an input")
                 }
                 "-p" | "--project-path" => {
                     project_dir = args.next().map(Self::validate_path);
@@ -160,19 +810,510 @@ impl Args {
                     target = args.next().map(Architecture::from);
                     Self::exit_if(target.is_none(), "--target requires an input")
                 }
+                "--maintainer" => {
+                    config.maintainer = Some(Self::required_input(args.next(), "--maintainer"))
+                }
+                "--section" => {
+                    config.section = Some(Self::required_input(args.next(), "--section"))
+                }
+                "--depends" => config
+                    .depends
+                    .push(Self::required_input(args.next(), "--depends")),
+                "--depends-file" => {
+                    let path = Self::required_input(args.next(), "--depends-file");
+                    let contents = std::fs::read_to_string(&path).unwrap_or_else(|err| {
+                        exit_err!("failed to read --depends-file: '{path}', {err}")
+                    });
+                    for dependency in config::dependency_list(&contents) {
+                        if !config.depends.contains(&dependency) {
+                            config.depends.push(dependency)
+                        }
+                    }
+                }
+                "--pre-depends" => {
+                    let dependency = Self::required_input(args.next(), "--pre-depends");
+                    config::validate_dependency(&dependency);
+                    config.pre_depends.push(dependency)
+                }
+                "--conflicts" => {
+                    let relation = Self::required_input(args.next(), "--conflicts");
+                    config::validate_dependency(&relation);
+                    config.conflicts.push(relation)
+                }
+                "--replaces" => {
+                    let relation = Self::required_input(args.next(), "--replaces");
+                    config::validate_dependency(&relation);
+                    config.replaces.push(relation)
+                }
+                "--provides" => {
+                    let relation = Self::required_input(args.next(), "--provides");
+                    config::validate_dependency(&relation);
+                    config.provides.push(relation)
+                }
+                "--multi-arch" => {
+                    let value = Self::required_input(args.next(), "--multi-arch");
+                    config::validate_multi_arch(&value);
+                    config.multi_arch = Some(value)
+                }
+                "--essential" => config.essential = Some(true),
+                "--distribution" => {
+                    config.distribution = Some(Self::required_input(args.next(), "--distribution"))
+                }
+                "--changelog-from-git" => changelog_from_git = true,
+                "--changelog-conventional" => changelog_conventional = true,
+                "--generate-copyright" => generate_copyright = true,
+                "--sbin" => sbin = true,
+                "--wrapper" => wrapper = true,
+                "--opt-layout" => {
+                    let vendor = Self::required_input(args.next(), "--opt-layout");
+                    if vendor.is_empty() || vendor.contains(['/', '\\']) || vendor.starts_with('.')
+                    {
+                        exit_err!("invalid --opt-layout vendor: '{vendor}'")
+                    }
+                    opt_vendor = Some(vendor)
+                }
+                "--doc-link" => doc_link = true,
+                "--print-path" => {
+                    print_path = Some(Self::parse_file_type(args.next(), "--print-path"))
+                }
+                "--emit" => {
+                    let file_type = Self::parse_file_type(args.next(), "--emit");
+                    if !file_type.is_text() {
+                        exit_err!("--emit only prints text file types, {file_type:?} is not one")
+                    }
+                    emit = Some(file_type)
+                }
+                "--relocate" => {
+                    let old = Self::validate_relocate(args.next());
+                    let new = Self::validate_relocate(args.next());
+                    relocate = Some((old, new))
+                }
+                "--list-filetypes" => {
+                    for file_type in FileType::ALL {
+                        println!("{file_type:?}")
+                    }
+                    crate::forge::warnings::exit(0);
+                }
+                "--binary-sha256" => {
+                    binary_sha256 = Some(Self::validate_sha256(
+                        Self::required_input(args.next(), "--binary-sha256"),
+                        "--binary-sha256",
+                    ))
+                }
+                "--binary-sha256-file" => {
+                    let path = Self::required_input(args.next(), "--binary-sha256-file");
+                    let contents = std::fs::read_to_string(&path).unwrap_or_else(|err| {
+                        exit_err!("failed to read --binary-sha256-file: '{path}', {err}")
+                    });
+                    // Accept `sha256sum` output where the hash is followed by the file name
+                    let hash = contents.split_whitespace().next().unwrap_or_default();
+                    binary_sha256 = Some(Self::validate_sha256(
+                        hash.to_string(),
+                        "--binary-sha256-file",
+                    ))
+                }
+                "--package" => packages.push(PathBuf::from(Self::required_input(
+                    args.next(),
+                    "--package",
+                ))),
+                "--prefer-dir" => {
+                    let name = Self::required_input(args.next(), "--prefer-dir");
+                    prefer_dir = Some(SearchDir::from_name(&name).unwrap_or_else(|| {
+                        exit_err!(
+                            "--prefer-dir must be one of: assets, build, debian, found: '{name}'"
+                        )
+                    }))
+                }
+                "--strict" => strict = true,
+                "--orig-tarball" => orig_tarball = true,
+                "--exclude" => excludes.push(Self::required_input(args.next(), "--exclude")),
+                "--reproducible" => reproducible = true,
+                "--stage-template" => {
+                    stage_template = Some(Self::validate_stage_template(Self::required_input(
+                        args.next(),
+                        "--stage-template",
+                    )))
+                }
+                "--check-ownership" => check_ownership = true,
+                "--shellcheck" => shellcheck = true,
+                "--fix-installed-size" => fix_installed_size = true,
+                "--color" => {
+                    let name = Self::required_input(args.next(), "--color");
+                    color = ColorChoice::from_name(&name).unwrap_or_else(|| {
+                        exit_err!("--color must be one of: auto, always, never, found: '{name}'")
+                    })
+                }
+                "--no-color" => color = ColorChoice::Never,
+                "--error-format" => {
+                    let name = Self::required_input(args.next(), "--error-format");
+                    error_format = ErrorFormat::from_name(&name).unwrap_or_else(|| {
+                        exit_err!("--error-format must be one of: text, json, found: '{name}'")
+                    });
+                    // Set right away so the remaining arguments are already reported in this format
+                    error::set_format(error_format)
+                }
+                "--allow-missing" => {
+                    allow_missing.push(Self::parse_file_type(args.next(), "--allow-missing"))
+                }
+                "--require" => {
+                    let file_type = Self::parse_file_type(args.next(), "--require");
+                    if file_type == FileType::Binary {
+                        exit_err!(
+                            "--require Binary is redundant, the project binary is always staged"
+                        )
+                    }
+                    if !require.contains(&file_type) {
+                        require.push(file_type)
+                    }
+                }
+                "--package-type" => {
+                    let name = Self::required_input(args.next(), "--package-type");
+                    package_type = Some(PackageType::from_name(&name).unwrap_or_else(|| {
+                        exit_err!(
+                            "--package-type must be one of: cli, gui, library, found: '{name}'"
+                        )
+                    }))
+                }
+                "--only" => {
+                    let file_type = Self::parse_file_type(args.next(), "--only");
+                    if !only.contains(&file_type) {
+                        only.push(file_type)
+                    }
+                }
+                "--skip" => {
+                    let file_type = Self::parse_file_type(args.next(), "--skip");
+                    if !skip.contains(&file_type) {
+                        skip.push(file_type)
+                    }
+                }
+                "--check" => check = true,
+                "--incremental" => incremental = true,
+                "--clean" => clean = true,
+                "--keep-staging" => keep_staging = true,
+                "--dump-config" => dump_config = true,
+                "--list-found" => list_found = true,
+                "--init" => init = true,
+                "--compress-binary" => compress_binary = true,
+                "--locale-desktop" => locale_desktop = true,
+                "--binary-copy" => {
+                    binary_copy.push(Self::required_input(args.next(), "--binary-copy"))
+                }
+                "--dest-root" => {
+                    let path = PathBuf::from(Self::required_input(args.next(), "--dest-root"));
+                    dest_root = Some(if path.is_absolute() {
+                        path
+                    } else {
+                        env::current_dir()
+                            .unwrap_or_else(|err| {
+                                exit_err!("failed to read the current directory: {err}")
+                            })
+                            .join(path)
+                    })
+                }
+                "--relative-symlinks" => relative_symlinks = true,
+                "--build" => build = true,
+                "--dpkg-deb" => dpkg_deb = true,
+                "--fakeroot" => fakeroot = true,
+                "--max-files" => {
+                    let count = Self::required_input(args.next(), "--max-files");
+                    max_files = match count.parse::<usize>() {
+                        Ok(count) if count > 0 => count,
+                        _ => exit_err!("--max-files must be a positive number, found: '{count}'"),
+                    }
+                }
+                "--max-size" => {
+                    let bytes = Self::required_input(args.next(), "--max-size");
+                    max_size = match bytes.parse::<u64>() {
+                        Ok(bytes) if bytes > 0 => Some(bytes),
+                        _ => exit_err!(
+                            "--max-size must be a positive number of bytes, found: '{bytes}'"
+                        ),
+                    }
+                }
+                "--compress-threshold" => {
+                    let bytes = Self::required_input(args.next(), "--compress-threshold");
+                    compress_threshold = bytes.parse::<u64>().unwrap_or_else(|_| {
+                        exit_err!(
+                            "--compress-threshold must be a number of bytes, found: '{bytes}'"
+                        )
+                    })
+                }
+                "--search-dir" => {
+                    let dir = PathBuf::from(Self::required_input(args.next(), "--search-dir"));
+                    if dir.is_absolute()
+                        || dir
+                            .components()
+                            .any(|component| component == Component::ParentDir)
+                        || dir.to_str().and_then(SearchDir::from_name).is_some()
+                    {
+                        exit_err!(
+                            "--search-dir must be a project subdirectory other than assets, build or debian, found: '{}'",
+                            dir.display()
+                        )
+                    }
+                    search_dirs.push(dir)
+                }
+                "--debian-dir" => {
+                    debian_dir = Some(PathBuf::from(Self::required_input(
+                        args.next(),
+                        "--debian-dir",
+                    )))
+                }
+                "--packages-index" => {
+                    packages_index = Some(Self::required_input(args.next(), "--packages-index"))
+                }
+                "--summary-json" => {
+                    summary_json = Some(Self::required_input(args.next(), "--summary-json"))
+                }
+                "--changes" => changes = true,
+                "--verify-deb" => verify_deb = true,
+                "--user-install" => user_install = true,
+                "--deb-compression" => {
+                    let name = Self::required_input(args.next(), "--deb-compression");
+                    deb_compression = Some(Compression::from_name(&name).unwrap_or_else(|| {
+                        exit_err!("--deb-compression must be one of: gz, xz, none, found: '{name}'")
+                    }))
+                }
+                "--owner" => owners.push(Self::parse_owner(Self::required_input(
+                    args.next(),
+                    "--owner",
+                ))),
+                "--features" => features.extend(
+                    Self::required_input(args.next(), "--features")
+                        .split([',', ' '])
+                        .filter(|feature| !feature.is_empty())
+                        .map(String::from),
+                ),
+                "--tool-timeout" => {
+                    let secs = Self::required_input(args.next(), "--tool-timeout");
+                    tool_timeout = match secs.parse::<u64>() {
+                        Ok(secs) if secs > 0 => Some(Duration::from_secs(secs)),
+                        _ => exit_err!(
+                            "--tool-timeout must be a positive number of seconds, found: '{secs}'"
+                        ),
+                    }
+                }
                 "-d" | "--dry-run" => dry_run = true,
+                "-q" | "--quiet" => quiet = true,
+                "--warnings-as-errors" => warnings_as_errors = true,
                 _ => {
-                    exit_err!("unknown argument: {arg}");
+                    exit_err!(kind: "InvalidArgument"; "unknown argument: {arg}");
                 }
             }
         }
+        color::set(color);
+
+        config.targets.extend(target);
+
+        // `--init` is how a directory becomes a project
+        if !init
+            && let Some(path) = project_dir.as_deref()
+            && !Self::looks_like_project(path)
+        {
+            exit_err!(
+                "'{}' doesn't look like a debforge project, expected a {TOML} or one of the assets, build or debian \
+                directories",
+                path.display()
+            )
+        }
+
+        if package_name.is_some() && !packages.is_empty() {
+            exit_err!(
+                "--package-name can not be combined with --package, each package is named by its control file"
+            )
+        }
+
+        if doc_link && opt_vendor.is_some() {
+            exit_err!("--doc-link can not be combined with --opt-layout")
+        }
+
+        if fakeroot && !dpkg_deb {
+            exit_err!(
+                "--fakeroot requires --dpkg-deb, the built-in writer already records every entry as owned by root"
+            )
+        }
+
+        if deb_compression.is_some() && !(build || dpkg_deb) {
+            exit_err!("--deb-compression requires --build")
+        }
+
+        if clean && (keep_staging || incremental) {
+            exit_err!(
+                "--clean can not be combined with {}",
+                if keep_staging {
+                    "--keep-staging"
+                } else {
+                    "--incremental"
+                }
+            )
+        }
+
+        if dest_root.is_some() && (build || dpkg_deb) {
+            exit_err!("--dest-root can not be combined with --build, it stages no control files")
+        }
+        if dest_root.is_some() && user_install {
+            exit_err!("--user-install can not be combined with --dest-root, it stages no postinst")
+        }
+
+        if !only.is_empty() && (build || dpkg_deb || incremental) {
+            exit_err!(
+                "--only stages an incomplete package and can not be combined with {}",
+                if incremental {
+                    "--incremental"
+                } else {
+                    "--build"
+                }
+            )
+        }
+
+        if require.contains(&FileType::Wrapper) && !wrapper {
+            exit_err!("--require Wrapper requires --wrapper, a wrapper is only staged with it")
+        }
+        for &file_type in package_type
+            .map(PackageType::required)
+            .unwrap_or_default()
+            .iter()
+            .chain(wrapper.then_some(&FileType::Wrapper))
+        {
+            if !require.contains(&file_type) {
+                require.push(file_type)
+            }
+        }
+        require.retain(|file_type| !REQUIRED_DEB_FILES.contains(file_type));
+        let is_required = |file_type: &FileType| {
+            REQUIRED_DEB_FILES.contains(file_type) || require.contains(file_type)
+        };
+
+        if let Some(file_type) = allow_missing
+            .iter()
+            .find(|file_type| !is_required(file_type))
+        {
+            exit_err!(
+                "--allow-missing only accepts required file types: {}, found: '{file_type:?}'",
+                REQUIRED_DEB_FILES
+                    .iter()
+                    .chain(require.iter())
+                    .map(|required| format!("{required:?}"))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )
+        }
+
+        if let Some(required) = skip
+            .iter()
+            .find(|&skipped| is_required(skipped) && !allow_missing.contains(skipped))
+        {
+            exit_err!(
+                "--skip {required:?} is a required file type, it also needs --allow-missing {required:?}"
+            )
+        }
+
+        if summary_json.is_some() && (dry_run || check) {
+            exit_err!(
+                "--summary-json reports what a run staged, it can not be combined with {}",
+                if dry_run { "--dry-run" } else { "--check" }
+            )
+        }
+
+        if packages_index.is_some() && !(build || dpkg_deb) {
+            exit_err!("--packages-index requires --build")
+        }
+        if changes && !(build || dpkg_deb) {
+            exit_err!("--changes requires --build")
+        }
+        if verify_deb && !(build || dpkg_deb) {
+            exit_err!("--verify-deb requires --build")
+        }
+
+        if changelog_conventional && !changelog_from_git {
+            exit_err!("--changelog-conventional requires --changelog-from-git")
+        }
+
+        if version_from_changelog
+            && let Some(conflict) = [
+                (version.is_some(), "--pkg-version"),
+                (version_suffix.is_some(), "--version-suffix"),
+                (changelog_from_git, "--changelog-from-git"),
+            ]
+            .into_iter()
+            .find_map(|(given, flag)| given.then_some(flag))
+        {
+            exit_err!(
+                "--version-from-changelog can not be combined with {conflict}, the changelog decides the version"
+            )
+        }
 
         Args {
             binary_name,
+            package_name,
             version,
+            version_suffix,
+            version_from_changelog,
             project_dir: project_dir.unwrap_or_else(Self::locate_valid_project_dir),
             dry_run,
-            architecture: target.unwrap_or_default(),
+            check,
+            warnings_as_errors,
+            strict,
+            changelog_from_git: changelog_from_git.then_some(changelog_conventional),
+            generate_copyright,
+            opt_vendor,
+            sbin,
+            wrapper,
+            stage_template,
+            dest_root,
+            print_path,
+            emit,
+            relocate,
+            binary_sha256,
+            packages,
+            prefer_dir,
+            search_dirs,
+            debian_dir,
+            max_files,
+            max_size,
+            compress_threshold,
+            orig_tarball,
+            excludes,
+            reproducible,
+            quiet,
+            check_ownership,
+            shellcheck,
+            fix_installed_size,
+            color,
+            error_format,
+            allow_missing,
+            require,
+            package_type,
+            only,
+            skip,
+            doc_link,
+            tool_timeout,
+            incremental,
+            clean,
+            keep_staging,
+            features,
+            dump_config,
+            list_found,
+            compress_binary,
+            binary_copy,
+            locale_desktop,
+            init,
+            owners,
+            relative_symlinks,
+            build: match (build, dpkg_deb) {
+                (_, true) => Some(DebBuilder::DpkgDeb(
+                    deb_compression.unwrap_or_default(),
+                    fakeroot,
+                )),
+                (true, false) => Some(DebBuilder::Native(deb_compression.unwrap_or_default())),
+                (false, false) => None,
+            },
+            packages_index,
+            summary_json,
+            changes,
+            verify_deb,
+            user_install,
+            config,
         }
     }
 }
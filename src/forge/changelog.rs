@@ -0,0 +1,232 @@
+use std::{
+    env, fs,
+    path::Path,
+    process::Command,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use super::{DebFiles, FileType, Variables, error::Error, tool, validate};
+use crate::fatal;
+
+const DAYS: [&str; 7] = ["Thu", "Fri", "Sat", "Sun", "Mon", "Tue", "Wed"];
+const MONTHS: [&str; 12] = [
+    "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+];
+
+/// Conventional commit types and the heading their entries are grouped under
+const COMMIT_GROUPS: [(&str, &str); 3] = [
+    ("feat", "New features"),
+    ("fix", "Bug fixes"),
+    ("perf", "Performance improvements"),
+];
+const OTHER_GROUP: &str = "Other changes";
+
+/// Seconds since the unix epoch, honoring `SOURCE_DATE_EPOCH` so reproducible builds get a stable date
+pub(super) fn build_timestamp() -> u64 {
+    env::var("SOURCE_DATE_EPOCH")
+        .ok()
+        .and_then(|epoch| epoch.trim().parse().ok())
+        .unwrap_or_else(|| {
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .expect("system clock is set after the unix epoch")
+                .as_secs()
+        })
+}
+
+/// Formats `timestamp` as the RFC 2822 date Debian changelogs expect, eg. `Thu, 01 Jan 1970 00:00:00 +0000`
+pub(super) fn rfc2822(timestamp: u64) -> String {
+    let days = timestamp / 86_400;
+    let secs = timestamp % 86_400;
+    let (year, month, day) = civil_from_days(days as i64);
+
+    format!(
+        "{}, {day:02} {} {year} {:02}:{:02}:{:02} +0000",
+        DAYS[(days % 7) as usize],
+        MONTHS[month as usize - 1],
+        secs / 3600,
+        secs % 3600 / 60,
+        secs % 60
+    )
+}
+
+/// Converts days since the unix epoch into a `(year, month, day)` civil date.
+/// See: https://howardhinnant.github.io/date_algorithms.html#civil_from_days
+pub(super) fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+fn git(project_dir: &Path, args: &[&str]) -> Result<Option<String>, Error> {
+    let output = tool::output(Command::new("git").args(args).current_dir(project_dir))
+        .map_err(|err| fatal!("failed to run git: {err}"))?;
+
+    Ok(output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).into_owned()))
+}
+
+/// Commit subjects since the most recent tag, or the entire history when the project has no tags
+fn commits_since_last_tag(project_dir: &Path) -> Result<Vec<String>, Error> {
+    let range = git(project_dir, &["describe", "--tags", "--abbrev=0"])?
+        .map(|tag| format!("{}..HEAD", tag.trim()));
+
+    let mut args = vec!["log", "--no-merges", "--format=%s"];
+    args.extend(range.as_deref());
+
+    let Some(log) = git(project_dir, &args)? else {
+        return Err(fatal!(
+            "failed to read git history at: '{}'",
+            project_dir.display()
+        ));
+    };
+
+    Ok(log
+        .lines()
+        .map(str::trim)
+        .filter(|subject| !subject.is_empty())
+        .map(String::from)
+        .collect())
+}
+
+/// Splits a conventional commit subject like `feat(args)!: add flag` into its type and description
+fn conventional(subject: &str) -> Option<(&str, &str)> {
+    let (prefix, description) = subject.split_once(':')?;
+    let kind = prefix.split(['(', '!']).next()?.trim();
+
+    (!kind.is_empty() && kind.chars().all(|c| c.is_ascii_alphabetic()))
+        .then(|| (kind, description.trim()))
+}
+
+fn format_changes(commits: &[String], group: bool) -> String {
+    let mut out = String::new();
+
+    if !group {
+        for subject in commits {
+            out.push_str(&format!("  * {subject}\n"));
+        }
+        return out;
+    }
+
+    let mut groups: Vec<(&str, Vec<&str>)> = Vec::new();
+    for subject in commits {
+        let (heading, description) = match conventional(subject) {
+            Some((kind, description)) => (
+                COMMIT_GROUPS
+                    .iter()
+                    .find(|(group_kind, _)| kind.eq_ignore_ascii_case(group_kind))
+                    .map_or(OTHER_GROUP, |&(_, heading)| heading),
+                description,
+            ),
+            None => (OTHER_GROUP, subject.as_str()),
+        };
+
+        match groups.iter_mut().find(|(existing, _)| *existing == heading) {
+            Some((_, entries)) => entries.push(description),
+            None => groups.push((heading, vec![description])),
+        }
+    }
+
+    groups.sort_by_key(|(heading, _)| {
+        COMMIT_GROUPS
+            .iter()
+            .position(|(_, group_heading)| group_heading == heading)
+            .unwrap_or(COMMIT_GROUPS.len())
+    });
+
+    for (heading, entries) in groups {
+        out.push_str(&format!("  * {heading}:\n"));
+        for description in entries {
+            out.push_str(&format!("    - {description}\n"));
+        }
+    }
+    out
+}
+
+/// The package name of the topmost entry of a changelog, eg. `pkg` from `pkg (1.0.0) unstable; urgency=low`
+pub(super) fn top_package(changelog: &str) -> Option<&str> {
+    let first = changelog.lines().find(|line| !line.trim().is_empty())?;
+    first.split_whitespace().next()
+}
+
+/// The version of the topmost entry of an existing changelog, eg. `1.0.0` from `pkg (1.0.0) unstable; urgency=low`
+pub(super) fn top_version(changelog: &str) -> Option<&str> {
+    let first = changelog.lines().find(|line| !line.trim().is_empty())?;
+    let (_, rest) = first.split_once('(')?;
+    rest.split_once(')').map(|(version, _)| version.trim())
+}
+
+/// The version of a changelog's first line, which must read `package (version) distribution; urgency=level`
+fn strict_top_version(changelog: &str) -> Result<&str, String> {
+    let first = changelog.lines().next().unwrap_or_default();
+    let malformed =
+        || format!("'{first}' does not read 'package (version) distribution; urgency=level'");
+
+    let (package, rest) = first.split_once(" (").ok_or_else(malformed)?;
+    let (version, rest) = rest.split_once(") ").ok_or_else(malformed)?;
+    let (distributions, urgency) = rest.split_once(';').ok_or_else(malformed)?;
+    if package.is_empty()
+        || package.contains(char::is_whitespace)
+        || version.is_empty()
+        || distributions.trim().is_empty()
+        || !urgency.trim_start().starts_with("urgency=")
+    {
+        return Err(malformed());
+    }
+    Ok(version)
+}
+
+impl Variables {
+    /// Reads `$Version` from the first line of the package's changelog for `--version-from-changelog`
+    pub(super) fn version_from_changelog(&self, files: &DebFiles) -> Result<String, Error> {
+        let Some(path) = files.files.get(&FileType::Changelog) else {
+            return Err(fatal!(
+                "--version-from-changelog requires a changelog file, none was found"
+            ));
+        };
+        let contents = fs::read_to_string(path)?;
+        let version = strict_top_version(&contents)
+            .map_err(|problem| fatal!("changelog: '{}' {problem}", path.display()))?;
+        if let Some(problem) = validate::version(version) {
+            return Err(fatal!(
+                "changelog: '{}' declares version: '{version}', {problem}",
+                path.display()
+            ));
+        }
+        Ok(version.to_string())
+    }
+
+    /// Builds a changelog entry for `$Version` from the git history since the last tag
+    pub(super) fn git_changelog_entry(&self, group: bool) -> Result<String, Error> {
+        let Some(maintainer) = self.config.maintainer.as_deref() else {
+            return Err(fatal!(
+                "--changelog-from-git requires a maintainer, supply one with --maintainer"
+            ));
+        };
+
+        let commits = commits_since_last_tag(&self.project_dir)?;
+        if commits.is_empty() {
+            return Err(fatal!(
+                "found no commits since the last tag to generate a changelog entry from"
+            ));
+        }
+
+        Ok(format!(
+            "{} ({}) {}; urgency=medium\n\n{}\n -- {maintainer}  {}\n",
+            self.package_name,
+            self.version,
+            self.config.distribution(),
+            format_changes(&commits, group),
+            self.date
+        ))
+    }
+}
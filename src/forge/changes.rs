@@ -0,0 +1,135 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use super::{Variables, control::Control, error::Error, hash};
+use crate::fatal;
+
+/// The topmost entry of a changelog, from its heading through its ` -- ` trailer line
+fn top_entry(changelog: &str) -> Vec<&str> {
+    let mut lines = changelog
+        .lines()
+        .skip_while(|line| line.trim().is_empty())
+        .collect::<Vec<_>>();
+    if let Some(trailer) = lines.iter().position(|line| line.starts_with(" -- ")) {
+        lines.truncate(trailer + 1);
+    }
+    lines
+}
+
+/// Formats `lines` as a deb822 multiline value, blank lines become ` .`
+fn multiline<S: AsRef<str>>(lines: impl IntoIterator<Item = S>) -> String {
+    lines
+        .into_iter()
+        .map(|line| match line.as_ref().trim().is_empty() {
+            true => "\n .".to_string(),
+            false => format!("\n {}", line.as_ref()),
+        })
+        .collect()
+}
+
+impl Variables {
+    /// Writes `{source}_{version}_{arch}.changes` next to the `.deb`s built for the current architecture. `built` holds
+    /// each package with its staged control file, the primary package first. `changelog` is the primary package's
+    /// staged changelog, its topmost entry becomes the `Changes` field
+    pub(super) fn write_changes(
+        &self,
+        built: &[(PathBuf, Control)],
+        changelog: Option<&str>,
+    ) -> Result<PathBuf, Error> {
+        let (_, primary) = built.first().expect("at least 1 package was built");
+        let field = |name: &str| {
+            primary.get(name).ok_or_else(|| {
+                fatal!("control file is missing the '{name}' field, can not write a .changes file")
+            })
+        };
+
+        // `Source` may carry the source version, eg. `foo (1.0.0)`
+        let source = match primary
+            .get("Source")
+            .and_then(|source| source.split_whitespace().next())
+        {
+            Some(source) => source,
+            None => field("Package")?,
+        };
+        let version = field("Version")?;
+        let mut architectures = Vec::new();
+        for (_, control) in built {
+            if let Some(architecture) = control.get("Architecture")
+                && !architectures.contains(&architecture)
+            {
+                architectures.push(architecture)
+            }
+        }
+
+        let mut changes = Control::parse("");
+        changes.set("Format", "1.8");
+        changes.set("Date", &self.date);
+        changes.set("Source", source);
+        changes.set(
+            "Binary",
+            &built
+                .iter()
+                .filter_map(|(_, control)| control.get("Package"))
+                .collect::<Vec<_>>()
+                .join(" "),
+        );
+        changes.set("Architecture", &architectures.join(" "));
+        changes.set("Version", version);
+        changes.set("Distribution", self.config.distribution());
+
+        let entry = changelog.map(top_entry).unwrap_or_default();
+        if let Some(urgency) = entry
+            .first()
+            .and_then(|heading| heading.split_once("urgency="))
+            .and_then(|(_, rest)| rest.split([',', ' ']).next())
+        {
+            changes.set("Urgency", urgency);
+        }
+        changes.set("Maintainer", field("Maintainer")?);
+        changes.set(
+            "Description",
+            &multiline(built.iter().filter_map(|(_, control)| {
+                let package = control.get("Package")?;
+                let synopsis = control.get("Description")?.lines().next()?;
+                Some(format!("{package} - {synopsis}"))
+            })),
+        );
+        if !entry.is_empty() {
+            changes.set("Changes", &multiline(entry));
+        }
+
+        let (mut sha1s, mut sha256s, mut files) = (Vec::new(), Vec::new(), Vec::new());
+        for (deb, control) in built {
+            let (md5, sha1, sha256) = hash::md5_sha1_sha256_file(deb)?;
+            let size = fs::metadata(deb)?.len();
+            let name = file_name(deb);
+            let section = control.get("Section").unwrap_or("misc");
+            let priority = control.get("Priority").unwrap_or("optional");
+
+            sha1s.push(format!("{sha1} {size} {name}"));
+            sha256s.push(format!("{sha256} {size} {name}"));
+            files.push(format!("{md5} {size} {section} {priority} {name}"));
+        }
+        changes.set("Checksums-Sha1", &multiline(sha1s));
+        changes.set("Checksums-Sha256", &multiline(sha256s));
+        changes.set("Files", &multiline(files));
+
+        // The epoch is never part of the file name
+        let version = version.split_once(':').map_or(version, |(_, rest)| rest);
+        let path = self.get_dist_dir().join(format!(
+            "{source}_{version}_{}.changes",
+            self.architecture.short()
+        ));
+        fs::write(&path, changes.render())?;
+        Ok(path)
+    }
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .expect("built packages have a file name")
+        .to_string_lossy()
+        .into_owned()
+}
@@ -0,0 +1,72 @@
+use std::{
+    fmt::Display,
+    io::{self, IsTerminal},
+    sync::OnceLock,
+};
+
+pub(crate) const RED: &str = "31";
+pub(crate) const YELLOW: &str = "33";
+pub(crate) const GREEN: &str = "32";
+
+/// When output is colored, set once from `--color`
+static CHOICE: OnceLock<ColorChoice> = OnceLock::new();
+
+#[derive(Clone, Copy, Default)]
+pub(crate) enum ColorChoice {
+    /// Colored when writing to a terminal and `NO_COLOR` is not set
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+impl ColorChoice {
+    pub(crate) fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "auto" => Some(Self::Auto),
+            "always" => Some(Self::Always),
+            "never" => Some(Self::Never),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            Self::Auto => "auto",
+            Self::Always => "always",
+            Self::Never => "never",
+        }
+    }
+}
+
+pub(crate) fn set(choice: ColorChoice) {
+    let _ = CHOICE.set(choice);
+}
+
+fn enabled(terminal: bool) -> bool {
+    match CHOICE.get().copied().unwrap_or_default() {
+        ColorChoice::Always => true,
+        ColorChoice::Never => false,
+        ColorChoice::Auto => {
+            terminal && std::env::var_os("NO_COLOR").is_none_or(|value| value.is_empty())
+        }
+    }
+}
+
+/// `text` in the ANSI color `code` when stderr is colored
+pub(crate) fn stderr(code: &str, text: impl Display) -> String {
+    paint(enabled(io::stderr().is_terminal()), code, text)
+}
+
+/// `text` in the ANSI color `code` when stdout is colored
+pub(crate) fn stdout(code: &str, text: impl Display) -> String {
+    paint(enabled(io::stdout().is_terminal()), code, text)
+}
+
+fn paint(enabled: bool, code: &str, text: impl Display) -> String {
+    if enabled {
+        format!("\x1b[{code}m{text}\x1b[0m")
+    } else {
+        text.to_string()
+    }
+}
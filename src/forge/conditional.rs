@@ -0,0 +1,183 @@
+use std::path::Path;
+
+use super::Variables;
+
+/// Evaluates `#if key==value` / `#if key!=value`, `#else` and `#endif` lines, which may nest. Only `arch` and `version`
+/// can be compared. Any other line starting with `#`, eg. `#if you edit this`, is an ordinary line
+pub(super) struct Conditions<'a> {
+    architecture: &'a str,
+    version: &'a str,
+    input: &'a Path,
+    /// Whether each open `#if` block is currently taken
+    open: Vec<bool>,
+}
+
+impl<'a> Conditions<'a> {
+    pub(super) fn new(vars: &'a Variables, input: &'a Path) -> Self {
+        Self {
+            architecture: vars.architecture.short(),
+            version: &vars.version,
+            input,
+            open: Vec::new(),
+        }
+    }
+
+    /// `Some(is_equal)` when `line` is a `#if` directive this syntax understands
+    fn condition(&self, line: &str) -> Option<bool> {
+        let condition = line.strip_prefix("#if ")?;
+        let (key, value, equal) = match condition.split_once("!=") {
+            Some((key, value)) => (key, value, false),
+            None => {
+                let (key, value) = condition.split_once("==")?;
+                (key, value, true)
+            }
+        };
+
+        let actual = match key.trim() {
+            "arch" => self.architecture,
+            "version" => self.version,
+            _ => return None,
+        };
+        Some((actual == value.trim()) == equal)
+    }
+
+    /// Whether `line` belongs in the output. Directive lines never do
+    pub(super) fn keep(&mut self, line: &str) -> bool {
+        let trimmed = line.trim();
+
+        if let Some(taken) = self.condition(trimmed) {
+            self.open.push(taken);
+            return false;
+        }
+        if !self.open.is_empty() {
+            match trimmed {
+                "#else" => {
+                    let last = self.open.last_mut().expect("checked not empty");
+                    *last = !*last;
+                    return false;
+                }
+                "#endif" => {
+                    self.open.pop();
+                    return false;
+                }
+                _ => (),
+            }
+        }
+        self.open.iter().all(|&taken| taken)
+    }
+
+    /// Errors if a `#if` was never closed
+    pub(super) fn finish(self) -> Result<(), String> {
+        if !self.open.is_empty() {
+            return Err(format!(
+                "{} '#if' block(s) missing an '#endif' in: '{}'",
+                self.open.len(),
+                self.input.display()
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn conditions() -> Conditions<'static> {
+        Conditions {
+            architecture: "amd64",
+            version: "1.2.3",
+            input: Path::new("debian/control"),
+            open: Vec::new(),
+        }
+    }
+
+    /// The lines of `input` that belong in the output
+    fn kept<'a>(conditions: &mut Conditions, input: &'a str) -> Vec<&'a str> {
+        input.lines().filter(|line| conditions.keep(line)).collect()
+    }
+
+    #[test]
+    fn nested() {
+        let mut conditions = conditions();
+        let input = "\
+a
+#if arch==amd64
+b
+#if version==1.2.3
+c
+#else
+d
+#endif
+#if version==0.1.0
+e
+#endif
+#else
+f
+#if version==1.2.3
+g
+#endif
+#endif
+h";
+        assert_eq!(kept(&mut conditions, input), ["a", "b", "c", "h"]);
+        assert_eq!(conditions.finish(), Ok(()));
+    }
+
+    #[test]
+    fn negated() {
+        let mut conditions = conditions();
+        let input = "\
+#if arch!=arm64
+a
+#else
+b
+#endif
+#if version != 1.2.3
+c
+#else
+d
+#endif";
+        assert_eq!(kept(&mut conditions, input), ["a", "d"]);
+        assert_eq!(conditions.finish(), Ok(()));
+    }
+
+    #[test]
+    fn unknown_condition() {
+        let mut conditions = conditions();
+        let input = "\
+#if you edit this
+#if distribution==stable
+#else
+#endif
+a";
+        assert_eq!(
+            kept(&mut conditions, input),
+            [
+                "#if you edit this",
+                "#if distribution==stable",
+                "#else",
+                "#endif",
+                "a"
+            ]
+        );
+        assert_eq!(conditions.finish(), Ok(()));
+    }
+
+    #[test]
+    fn unterminated() {
+        let mut conditions = conditions();
+        let input = "\
+#if arch==amd64
+a
+#if version==1.2.3
+b
+#endif";
+        assert_eq!(kept(&mut conditions, input), ["a", "b"]);
+        assert_eq!(
+            conditions.finish(),
+            Err(String::from(
+                "1 '#if' block(s) missing an '#endif' in: 'debian/control'"
+            ))
+        );
+    }
+}
@@ -0,0 +1,353 @@
+use std::{fs, io, path::Path};
+
+use toml::Table;
+
+use crate::{
+    args::Architecture,
+    exit_err,
+    forge::{FileType, TOML},
+};
+
+const DEFAULT_DISTRIBUTION: &str = "unstable";
+pub(crate) const PROJECT_CONFIG: &str = ".debforge.toml";
+/// Values Debian allows for the `Multi-Arch` field
+pub(crate) const MULTI_ARCH_VALUES: [&str; 4] = ["same", "foreign", "allowed", "no"];
+
+/// Packaging settings that can be declared in Cargo.toml's `[package.metadata.debforge]` table, a `.debforge.toml`
+/// file or supplied on the command line. Command line input always takes precedence, then `.debforge.toml`
+#[derive(Default, Clone)]
+pub(crate) struct Config {
+    pub(crate) targets: Vec<Architecture>,
+    /// Architectures the project can be packaged for, any other target is rejected. Empty allows every target
+    pub(crate) supported_architectures: Vec<Architecture>,
+    pub(crate) maintainer: Option<String>,
+    pub(crate) section: Option<String>,
+    pub(crate) homepage: Option<String>,
+    pub(crate) distribution: Option<String>,
+    pub(crate) depends: Vec<String>,
+    /// Merged into the control file's `Pre-Depends` field, each checked by `validate_dependency`
+    pub(crate) pre_depends: Vec<String>,
+    /// Merged into the control file's `Conflicts` field
+    pub(crate) conflicts: Vec<String>,
+    /// Merged into the control file's `Replaces` field
+    pub(crate) replaces: Vec<String>,
+    /// Merged into the control file's `Provides` field
+    pub(crate) provides: Vec<String>,
+    pub(crate) license: Option<String>,
+    pub(crate) authors: Vec<String>,
+    /// Icon file names mapped to the icon size they are staged as, bypassing the file name heuristic
+    pub(crate) icons: Vec<(String, FileType)>,
+    /// Replaces the control file's `Multi-Arch` field, one of `MULTI_ARCH_VALUES`
+    pub(crate) multi_arch: Option<String>,
+    /// Replaces the control file's `Essential` field
+    pub(crate) essential: Option<bool>,
+}
+
+impl Config {
+    /// Fills any setting that `self` did not set from `lower`
+    pub(crate) fn or(self, lower: Config) -> Self {
+        fn or_vec<T>(higher: Vec<T>, lower: Vec<T>) -> Vec<T> {
+            if higher.is_empty() { lower } else { higher }
+        }
+
+        Self {
+            targets: or_vec(self.targets, lower.targets),
+            supported_architectures: or_vec(
+                self.supported_architectures,
+                lower.supported_architectures,
+            ),
+            maintainer: self.maintainer.or(lower.maintainer),
+            section: self.section.or(lower.section),
+            homepage: self.homepage.or(lower.homepage),
+            distribution: self.distribution.or(lower.distribution),
+            depends: or_vec(self.depends, lower.depends),
+            pre_depends: or_vec(self.pre_depends, lower.pre_depends),
+            conflicts: or_vec(self.conflicts, lower.conflicts),
+            replaces: or_vec(self.replaces, lower.replaces),
+            provides: or_vec(self.provides, lower.provides),
+            license: self.license.or(lower.license),
+            authors: or_vec(self.authors, lower.authors),
+            icons: or_vec(self.icons, lower.icons),
+            multi_arch: self.multi_arch.or(lower.multi_arch),
+            essential: self.essential.or(lower.essential),
+        }
+    }
+
+    /// Exits when a target is not one of the declared `supported-architectures`
+    pub(crate) fn check_supported_targets(&self) {
+        if self.supported_architectures.is_empty() {
+            return;
+        }
+        if let Some(target) = self
+            .targets
+            .iter()
+            .find(|target| !self.supported_architectures.contains(target))
+        {
+            exit_err!(
+                "this project does not support target: {}, supported architectures: {}",
+                target.short(),
+                self.supported_architectures
+                    .iter()
+                    .map(|architecture| architecture.short())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )
+        }
+    }
+
+    pub(crate) fn distribution(&self) -> &str {
+        self.distribution.as_deref().unwrap_or(DEFAULT_DISTRIBUTION)
+    }
+
+    /// Reads the settings declared in `.debforge.toml`, which uses the same keys as `[package.metadata.debforge]`
+    pub(super) fn from_project_file(project_dir: &Path) -> io::Result<Self> {
+        let contents = match fs::read_to_string(project_dir.join(PROJECT_CONFIG)) {
+            Ok(contents) => contents,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(err) => return Err(err),
+        };
+
+        let table = contents
+            .parse::<Table>()
+            .unwrap_or_else(|err| exit_err!("failed to parse {PROJECT_CONFIG}: {err}"));
+        Ok(Self::from_metadata(&table, PROJECT_CONFIG))
+    }
+
+    /// Reads the settings declared in the `[package.metadata.debforge]` table, unknown keys are ignored. `source`
+    /// names the file the table was read from
+    pub(super) fn from_metadata(table: &Table, source: &str) -> Self {
+        Self {
+            targets: string_array(table, "targets", source)
+                .into_iter()
+                .map(Architecture::from)
+                .collect(),
+            supported_architectures: string_array(table, "supported-architectures", source)
+                .into_iter()
+                .map(Architecture::from)
+                .collect(),
+            maintainer: string(table, "maintainer", source),
+            section: string(table, "section", source),
+            homepage: None,
+            distribution: string(table, "distribution", source),
+            depends: string_array(table, "depends", source),
+            pre_depends: string_array(table, "pre-depends", source)
+                .into_iter()
+                .inspect(|dependency| validate_dependency(dependency))
+                .collect(),
+            conflicts: string_array(table, "conflicts", source)
+                .into_iter()
+                .inspect(|relation| validate_dependency(relation))
+                .collect(),
+            replaces: string_array(table, "replaces", source)
+                .into_iter()
+                .inspect(|relation| validate_dependency(relation))
+                .collect(),
+            provides: string_array(table, "provides", source)
+                .into_iter()
+                .inspect(|relation| validate_dependency(relation))
+                .collect(),
+            icons: icons(table),
+            multi_arch: string(table, "multi-arch", source)
+                .inspect(|value| validate_multi_arch(value)),
+            essential: table.get("essential").map(|value| {
+                value.as_bool().unwrap_or_else(|| {
+                    exit_err!("'essential' in {source} must be a boolean, found: {value}")
+                })
+            }),
+            ..Default::default()
+        }
+    }
+
+    /// Reads the defaults that can be inferred from the `[package]` table itself
+    pub(super) fn from_package(package: &Table) -> Self {
+        Self {
+            maintainer: string_array(package, "authors", TOML).into_iter().next(),
+            homepage: string(package, "homepage", TOML),
+            license: string(package, "license", TOML),
+            authors: string_array(package, "authors", TOML),
+            ..Default::default()
+        }
+    }
+}
+
+/// Picks the `[[bin]]` target to package when a crate declares any, keeping only those whose `required-features`
+/// are enabled by `features` and the crate's default features, preferring those gated on a feature in `features`.
+/// `None` when the crate declares no `[[bin]]` targets
+pub(super) fn select_binary(manifest: &Table, features: &[String]) -> Option<String> {
+    let bins = manifest
+        .get("bin")?
+        .as_array()
+        .filter(|bins| !bins.is_empty())?;
+
+    let declared = manifest
+        .get("features")
+        .and_then(toml::Value::as_table)
+        .cloned()
+        .unwrap_or_default();
+
+    // Enabling a feature enables every feature it lists, dependency features like `dep/feature` are not followed
+    let mut enabled = features.to_vec();
+    enabled.push(String::from("default"));
+    let mut i = 0;
+    while let Some(feature) = enabled.get(i).cloned() {
+        for implied in string_array(&declared, &feature, TOML) {
+            if !implied.contains(['/', ':']) && !enabled.contains(&implied) {
+                enabled.push(implied);
+            }
+        }
+        i += 1;
+    }
+
+    let bins = bins
+        .iter()
+        .filter_map(toml::Value::as_table)
+        .map(|bin| {
+            let Some(name) = string(bin, "name", TOML) else {
+                exit_err!("every [[bin]] in {TOML} must have a 'name'")
+            };
+            (name, string_array(bin, "required-features", TOML))
+        })
+        .collect::<Vec<_>>();
+
+    let mut candidates = bins
+        .iter()
+        .filter(|(_, required)| required.iter().all(|feature| enabled.contains(feature)))
+        .collect::<Vec<_>>();
+
+    // A binary gated on an explicitly requested feature is the one being packaged
+    if candidates.len() > 1 {
+        let requested = candidates
+            .iter()
+            .copied()
+            .filter(|(_, required)| required.iter().any(|feature| features.contains(feature)))
+            .collect::<Vec<_>>();
+        if !requested.is_empty() {
+            candidates = requested;
+        }
+    }
+    let candidates = candidates
+        .into_iter()
+        .map(|(name, _)| name.as_str())
+        .collect::<Vec<_>>();
+
+    match candidates[..] {
+        [name] => Some(name.to_string()),
+        [] => exit_err!(
+            "no [[bin]] in {TOML} has its required-features enabled, enable them with --features or supply \
+            --binary-name. Candidates: {}",
+            bins.iter()
+                .map(|(name, required)| format!("{name} (requires: {})", required.join(", ")))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+        _ => exit_err!(
+            "found more than 1 [[bin]] in {TOML} that can be packaged, supply --binary-name. Candidates: {}",
+            candidates.join(", ")
+        ),
+    }
+}
+
+/// Reads the `icons` table, eg. `"logo.png" = 256`
+fn icons(table: &Table) -> Vec<(String, FileType)> {
+    let Some(value) = table.get("icons") else {
+        return Vec::new();
+    };
+    let Some(icons) = value.as_table() else {
+        exit_err!("'icons' must be a table of file names to icon sizes, found: {value}")
+    };
+
+    icons
+        .iter()
+        .map(|(file_name, size)| {
+            let icon = size
+                .as_integer()
+                .and_then(|size| FileType::icon_for_size(size.to_string().as_str()))
+                .unwrap_or_else(|| {
+                    exit_err!(
+                        "icon: '{file_name}' has unsupported size: {size}, supported: {}",
+                        FileType::icon_sizes().join(", ")
+                    )
+                });
+            (file_name.clone(), icon)
+        })
+        .collect()
+}
+
+pub(crate) fn validate_multi_arch(value: &str) {
+    if !MULTI_ARCH_VALUES.contains(&value) {
+        exit_err!(
+            "Multi-Arch must be one of: {}, found: '{value}'",
+            MULTI_ARCH_VALUES.join(", ")
+        )
+    }
+}
+
+/// Minimal check of a dependency against deb-control(5): alternatives separated by `|`, each a package name with an
+/// optional `:arch` qualifier and an optional `(op version)` restriction
+pub(crate) fn validate_dependency(dependency: &str) {
+    const OPERATORS: [&str; 5] = ["<<", "<=", ">=", ">>", "="];
+
+    let valid = dependency.split('|').all(|alternative| {
+        let (name, restriction) = match alternative.split_once('(') {
+            Some((name, restriction)) => (name.trim(), Some(restriction.trim())),
+            None => (alternative.trim(), None),
+        };
+        let name = name.split_once(':').map_or(name, |(name, _)| name);
+        let name_valid = name.len() >= 2
+            && name.starts_with(|c: char| c.is_ascii_lowercase() || c.is_ascii_digit())
+            && name.chars().all(|c| {
+                c.is_ascii_lowercase() || c.is_ascii_digit() || matches!(c, '+' | '-' | '.')
+            });
+        let restriction_valid = restriction.is_none_or(|restriction| {
+            restriction
+                .strip_suffix(')')
+                .and_then(|inner| OPERATORS.into_iter().find_map(|op| inner.strip_prefix(op)))
+                .is_some_and(|version| {
+                    let version = version.trim();
+                    !version.is_empty() && !version.contains(char::is_whitespace)
+                })
+        });
+        name_valid && restriction_valid
+    });
+    if !valid {
+        exit_err!("invalid dependency: '{dependency}', expected eg. 'package (>= version)'")
+    }
+}
+
+/// Dependencies listed 1 per line, `#` starts a comment and blank lines are skipped
+pub(crate) fn dependency_list(contents: &str) -> Vec<String> {
+    contents
+        .lines()
+        .map(|line| line.split_once('#').map_or(line, |(entry, _)| entry).trim())
+        .filter(|entry| !entry.is_empty())
+        .inspect(|entry| validate_dependency(entry))
+        .map(String::from)
+        .collect()
+}
+
+/// `source` names the file `table` was read from for the type error
+pub(super) fn string(table: &Table, key: &str, source: &str) -> Option<String> {
+    let value = table.get(key)?;
+    let Some(str) = value.as_str() else {
+        exit_err!("'{key}' in {source} must be a string, found: {value}")
+    };
+    Some(str.to_string())
+}
+
+/// `source` names the file `table` was read from for the type error
+pub(super) fn string_array(table: &Table, key: &str, source: &str) -> Vec<String> {
+    let Some(value) = table.get(key) else {
+        return Vec::new();
+    };
+    let Some(array) = value.as_array() else {
+        exit_err!("'{key}' in {source} must be an array of strings, found: {value}")
+    };
+
+    array
+        .iter()
+        .map(|item| match item.as_str() {
+            Some(str) => str.to_string(),
+            None => exit_err!("'{key}' in {source} must be an array of strings, found: {value}"),
+        })
+        .collect()
+}
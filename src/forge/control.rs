@@ -0,0 +1,296 @@
+use std::{fs, io, path::Path};
+
+use super::{config::Config, summary};
+use crate::warn;
+
+/// Fields of a debhelper source paragraph a binary package inherits when its own paragraph does not declare them, as
+/// dpkg-gencontrol does. Every other source field, eg. `Build-Depends`, only describes the source package
+const INHERITED_FIELDS: [&str; 4] = ["Maintainer", "Section", "Priority", "Homepage"];
+
+/// A single paragraph deb822 control file, kept as ordered fields so the rendered output matches the user's layout
+pub(super) struct Control {
+    fields: Vec<(String, String)>,
+}
+
+impl Control {
+    pub(super) fn parse(contents: &str) -> Self {
+        let mut fields: Vec<(String, String)> = Vec::new();
+
+        for line in contents.lines() {
+            if line.starts_with([' ', '\t']) {
+                if let Some((_, value)) = fields.last_mut() {
+                    value.push('\n');
+                    value.push_str(line);
+                }
+                continue;
+            }
+
+            if let Some((key, value)) = line.split_once(':') {
+                fields.push((key.trim().to_string(), value.trim().to_string()));
+            }
+        }
+
+        Self { fields }
+    }
+
+    /// Binary control of `package` from a debhelper style `debian/control`, a source paragraph followed by a paragraph
+    /// for each binary package. `Architecture: any` or `all` becomes the staged `architecture`. Contents that do not
+    /// start with a source paragraph are parsed as a single paragraph. `None` when no paragraph is for `package`
+    pub(super) fn parse_binary(contents: &str, package: &str, architecture: &str) -> Option<Self> {
+        let mut paragraphs = paragraphs(contents)
+            .into_iter()
+            .map(|paragraph| Self::parse(&paragraph));
+        let Some(source) = paragraphs.next() else {
+            return Some(Self::parse(contents));
+        };
+        if source.get("Source").is_none() || source.get("Package").is_some() {
+            return Some(Self::parse(contents));
+        }
+
+        let mut binary = paragraphs.find(|paragraph| paragraph.get("Package") == Some(package))?;
+        for field in INHERITED_FIELDS {
+            if let Some(value) = source.get(field) {
+                binary.set_if_absent(field, value);
+            }
+        }
+        if let Some("any" | "all") = binary.get("Architecture") {
+            binary.set("Architecture", architecture);
+        }
+        Some(binary)
+    }
+
+    pub(super) fn get(&self, key: &str) -> Option<&str> {
+        self.fields
+            .iter()
+            .find(|(field, _)| field.eq_ignore_ascii_case(key))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Fields in the order the control file declared them
+    pub(super) fn fields(&self) -> &[(String, String)] {
+        &self.fields
+    }
+
+    pub(super) fn set_if_absent(&mut self, key: &str, value: &str) {
+        if self.get(key).is_none() {
+            self.fields.push((key.to_string(), value.to_string()));
+        }
+    }
+
+    /// Sets `key` to `value`, replacing any value the control file declared
+    pub(super) fn set(&mut self, key: &str, value: &str) {
+        match self
+            .fields
+            .iter_mut()
+            .find(|(field, _)| field.eq_ignore_ascii_case(key))
+        {
+            Some((_, existing)) => *existing = value.to_string(),
+            None => self.fields.push((key.to_string(), value.to_string())),
+        }
+    }
+
+    pub(super) fn remove(&mut self, key: &str) {
+        self.fields
+            .retain(|(field, _)| !field.eq_ignore_ascii_case(key));
+    }
+
+    /// Appends each dependency in `items` to the comma separated field `key` unless it is already listed
+    pub(super) fn merge_list(&mut self, key: &str, items: &[String]) {
+        if items.is_empty() {
+            return;
+        }
+
+        let index = self
+            .fields
+            .iter()
+            .position(|(field, _)| field.eq_ignore_ascii_case(key))
+            .unwrap_or_else(|| {
+                self.fields.push((key.to_string(), String::new()));
+                self.fields.len() - 1
+            });
+
+        let value = &mut self.fields[index].1;
+        for item in items {
+            if !value.split(',').any(|existing| existing.trim() == item) {
+                if !value.trim().is_empty() {
+                    value.push_str(", ");
+                }
+                value.push_str(item);
+            }
+        }
+    }
+
+    /// Fills in fields the control file did not declare from the resolved `Config`, `Multi-Arch` and `Essential`
+    /// replace any declared value
+    pub(super) fn apply_config(&mut self, config: &Config) {
+        if let Some(maintainer) = config.maintainer.as_deref() {
+            self.set_if_absent("Maintainer", maintainer);
+        }
+        if let Some(section) = config.section.as_deref() {
+            self.set_if_absent("Section", section);
+        }
+        if let Some(homepage) = config.homepage.as_deref() {
+            self.set_if_absent("Homepage", homepage);
+        }
+        self.merge_list("Depends", &config.depends);
+        self.merge_list("Pre-Depends", &config.pre_depends);
+        self.merge_list("Conflicts", &config.conflicts);
+        self.merge_list("Replaces", &config.replaces);
+        self.merge_list("Provides", &config.provides);
+        if let Some(multi_arch) = config.multi_arch.as_deref() {
+            self.set("Multi-Arch", multi_arch);
+        }
+        if let Some(essential) = config.essential {
+            self.set("Essential", if essential { "yes" } else { "no" });
+        }
+    }
+
+    pub(super) fn render(&self) -> String {
+        let mut out = String::new();
+        for (key, value) in self.fields.iter() {
+            out.push_str(key);
+            out.push(':');
+            if !value.is_empty() && !value.starts_with('\n') {
+                out.push(' ');
+            }
+            out.push_str(value);
+            out.push('\n');
+        }
+        out
+    }
+}
+
+/// Splits deb822 `contents` on the blank lines separating its paragraphs
+fn paragraphs(contents: &str) -> Vec<String> {
+    let mut paragraphs = vec![String::new()];
+    for line in contents.lines() {
+        let current = paragraphs.last_mut().expect("never empty");
+        if !line.trim().is_empty() {
+            current.push_str(line);
+            current.push('\n');
+        } else if !current.is_empty() {
+            paragraphs.push(String::new())
+        }
+    }
+    paragraphs.retain(|paragraph| !paragraph.is_empty());
+    paragraphs
+}
+
+/// Compares the `Installed-Size`, in KiB, the staged control file of `package` declares with the size of the files staged under
+/// `stage_root`. A mismatch over 10% is reported with both values, or with `fix` any mismatch is corrected in place
+pub(super) fn check_installed_size(
+    package: &str,
+    control_path: &Path,
+    stage_root: &Path,
+    fix: bool,
+    quiet: bool,
+) -> io::Result<()> {
+    let control = Control::parse(&fs::read_to_string(control_path)?);
+    let Some(declared) = control.get("Installed-Size").map(String::from) else {
+        return Ok(());
+    };
+    let computed = summary::installed_bytes(stage_root)?.div_ceil(1024);
+
+    let Ok(declared) = declared.parse::<u64>() else {
+        if fix {
+            return set_installed_size(control, control_path, computed, &declared, quiet);
+        }
+        warn!(
+            "Installed-Size: '{declared}' of package: '{package}' is not a number of KiB, the staged files total \
+            {computed} KiB"
+        );
+        return Ok(());
+    };
+
+    let difference = declared.abs_diff(computed);
+    if fix && difference != 0 {
+        let declared = declared.to_string();
+        return set_installed_size(control, control_path, computed, &declared, quiet);
+    }
+    if difference > 1 && difference * 10 > computed {
+        warn!(
+            "Installed-Size: {declared} KiB of package: '{package}' is stale, the staged files total {computed} KiB, \
+            supply --fix-installed-size to correct it"
+        )
+    }
+    Ok(())
+}
+
+fn set_installed_size(
+    mut control: Control,
+    control_path: &Path,
+    computed: u64,
+    declared: &str,
+    quiet: bool,
+) -> io::Result<()> {
+    control.set("Installed-Size", &computed.to_string());
+    fs::write(control_path, control.render())?;
+    if !quiet {
+        println!("Corrected Installed-Size: '{declared}' to: {computed} KiB")
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DEBHELPER_CONTROL: &str = "\
+Source: my-app
+Section: utils
+Priority: optional
+Maintainer: Jane Doe <jane@example.com>
+Build-Depends: debhelper-compat (= 13), cargo
+Standards-Version: 4.6.2
+Homepage: https://example.com
+
+Package: my-app
+Architecture: any
+Depends: ${shlibs:Depends}, ${misc:Depends}
+Description: An example application
+ Long description.
+
+Package: my-app-data
+Architecture: all
+Section: misc
+Description: Data files of my-app
+";
+
+    #[test]
+    fn parse_binary_selects_package_paragraph() {
+        let control = Control::parse_binary(DEBHELPER_CONTROL, "my-app-data", "arm64").unwrap();
+        assert_eq!(
+            control.render(),
+            "Package: my-app-data\n\
+            Architecture: arm64\n\
+            Section: misc\n\
+            Description: Data files of my-app\n\
+            Maintainer: Jane Doe <jane@example.com>\n\
+            Priority: optional\n\
+            Homepage: https://example.com\n"
+        );
+
+        let control = Control::parse_binary(DEBHELPER_CONTROL, "my-app", "amd64").unwrap();
+        assert_eq!(control.get("Architecture"), Some("amd64"));
+        assert_eq!(control.get("Section"), Some("utils"));
+        assert_eq!(
+            control.get("Description"),
+            Some("An example application\n Long description.")
+        );
+        for field in ["Source", "Build-Depends", "Standards-Version"] {
+            assert_eq!(control.get(field), None, "{field}");
+        }
+    }
+
+    #[test]
+    fn parse_binary_without_matching_paragraph() {
+        assert!(Control::parse_binary(DEBHELPER_CONTROL, "other", "amd64").is_none());
+    }
+
+    #[test]
+    fn parse_binary_single_paragraph() {
+        let contents = "Package: my-app\nVersion: 1.0\nArchitecture: all\n";
+        let control = Control::parse_binary(contents, "other", "amd64").unwrap();
+        assert_eq!(control.render(), contents);
+    }
+}
@@ -0,0 +1,148 @@
+use std::{fs, path::PathBuf};
+
+use super::{FileType, Variables, changelog, error::Error};
+use crate::warn;
+
+const FORMAT: &str = "https://www.debian.org/doc/packaging-manuals/copyright-format/1.0/";
+
+/// Directory of copyright fragments, eg. for vendored dependencies, merged into the package's copyright file
+pub(super) const FRAGMENT_DIR: &str = "copyright.d";
+
+/// Converts a Cargo SPDX license expression into DEP-5's short license syntax, eg. `MIT OR Apache-2.0` becomes
+/// `MIT or Apache-2.0`. Cargo's deprecated `MIT/Apache-2.0` form is treated as `or`
+fn dep5_license(expression: &str) -> String {
+    expression
+        .split('/')
+        .map(|alternative| {
+            alternative
+                .split_whitespace()
+                .map(|token| match token {
+                    "OR" => "or",
+                    "AND" => "and",
+                    token => token,
+                })
+                .collect::<Vec<_>>()
+                .join(" ")
+        })
+        .collect::<Vec<_>>()
+        .join(" or ")
+}
+
+/// Splits a deb822 file into its paragraphs of lines
+fn paragraphs(contents: &str) -> Vec<Vec<&str>> {
+    contents
+        .split('\n')
+        .collect::<Vec<_>>()
+        .split(|line| line.trim().is_empty())
+        .filter(|paragraph| !paragraph.is_empty())
+        .map(<[_]>::to_vec)
+        .collect()
+}
+
+/// Merges copyright fragments into `base`. Only `base`'s header paragraph is kept, every `Files` paragraph follows in
+/// order, and each license text is moved into a single standalone `License` paragraph so identical texts shared by
+/// several fragments appear once
+fn merge(base: &str, fragments: &[String]) -> String {
+    let mut header = None;
+    let mut files = Vec::new();
+    let mut licenses: Vec<(&str, String)> = Vec::new();
+
+    for (i, contents) in std::iter::once(base)
+        .chain(fragments.iter().map(String::as_str))
+        .enumerate()
+    {
+        for paragraph in paragraphs(contents) {
+            if paragraph[0].starts_with("Format:") {
+                if i == 0 {
+                    header = Some(paragraph.join("\n"));
+                }
+                continue;
+            }
+
+            let Some(at) = paragraph
+                .iter()
+                .position(|line| line.starts_with("License:"))
+            else {
+                files.push(paragraph.join("\n"));
+                continue;
+            };
+            let name = paragraph[at]["License:".len()..].trim();
+            let body_len = paragraph[at + 1..]
+                .iter()
+                .take_while(|line| line.starts_with([' ', '\t']))
+                .count();
+            let body = paragraph[at + 1..at + 1 + body_len].join("\n");
+
+            if !body.is_empty() {
+                match licenses.iter().find(|(existing, _)| *existing == name) {
+                    Some((_, existing)) if *existing != body => warn!(
+                        "copyright fragments declare different texts for license: '{name}', keeping the first"
+                    ),
+                    Some(_) => (),
+                    None => licenses.push((name, body)),
+                }
+            }
+
+            // Standalone license paragraphs are rebuilt from `licenses`
+            if at != 0 {
+                let mut kept = paragraph[..=at].to_vec();
+                kept.extend_from_slice(&paragraph[at + 1 + body_len..]);
+                files.push(kept.join("\n"));
+            }
+        }
+    }
+
+    let mut out = header.into_iter().chain(files).collect::<Vec<_>>();
+    out.extend(
+        licenses
+            .into_iter()
+            .map(|(name, body)| format!("License: {name}\n{body}")),
+    );
+    let mut out = out.join("\n\n");
+    out.push('\n');
+    out
+}
+
+impl Variables {
+    /// Overwrites the staged copyright file with `base` merged with every fragment found in a `copyright.d` directory
+    pub(super) fn write_merged_copyright(
+        &self,
+        base: &str,
+        fragments: &[PathBuf],
+    ) -> Result<(), Error> {
+        let fragments = fragments
+            .iter()
+            .map(|fragment| self.render_text(FileType::Copyright, fragment))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        fs::create_dir_all(self.get_file_type_path(FileType::Copyright))?;
+        Ok(fs::write(
+            self.get_output_path(FileType::Copyright),
+            merge(base, &fragments),
+        )?)
+    }
+
+    /// Builds a minimal DEP-5 copyright file from Cargo.toml's `license` and `authors`, `None` if either is missing
+    pub(super) fn generated_copyright(&self) -> Option<String> {
+        let license = self.config.license.as_deref()?;
+        if self.config.authors.is_empty() {
+            return None;
+        }
+
+        let days = changelog::build_timestamp() / 86_400;
+        let (year, ..) = changelog::civil_from_days(days as i64);
+
+        let mut out = format!("Format: {FORMAT}\nUpstream-Name: {}\n", self.package_name);
+        if let Some(homepage) = self.config.homepage.as_deref() {
+            out.push_str(&format!("Source: {homepage}\n"));
+        }
+
+        out.push_str("\nFiles: *\n");
+        for (i, author) in self.config.authors.iter().enumerate() {
+            let key = if i == 0 { "Copyright:" } else { "          " };
+            out.push_str(&format!("{key} {year} {author}\n"));
+        }
+        out.push_str(&format!("License: {}\n", dep5_license(license)));
+        Some(out)
+    }
+}
@@ -0,0 +1,440 @@
+use std::{
+    fs,
+    io::{self, BufReader, BufWriter, Write},
+    path::{Component, Path, PathBuf},
+    process::Command,
+    sync::{Mutex, OnceLock},
+    time::UNIX_EPOCH,
+};
+
+use flate2::{Compression as GzLevel, GzBuilder};
+use tar::{Builder, EntryType, Header};
+
+use super::{FileType, PKG_NAME, Variables, changelog, control::Control, error::Error, tool};
+use crate::fatal;
+
+/// Symlinks a host without unix symlinks could not stage, added to the data tarball as link entries instead.
+/// Initialized when packages are built with the native writer
+pub(super) static DEFERRED_LINKS: OnceLock<Mutex<Vec<(PathBuf, PathBuf)>>> = OnceLock::new();
+
+const DEBIAN_BINARY: &[u8] = b"2.0\n";
+const AR_MAGIC: &[u8] = b"!<arch>\n";
+/// Maintainer scripts dpkg requires to be executable
+pub(super) const SCRIPTS: [&str; 5] = ["preinst", "postinst", "prerm", "postrm", "config"];
+
+/// Compression applied to the control and data members of a built `.deb`
+#[derive(Clone, Copy, Default)]
+pub(crate) enum Compression {
+    #[default]
+    Gz,
+    Xz,
+    None,
+}
+
+impl Compression {
+    pub(crate) fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "gz" | "gzip" => Some(Self::Gz),
+            "xz" => Some(Self::Xz),
+            "none" => Some(Self::None),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            Self::Gz => "gz",
+            Self::Xz => "xz",
+            Self::None => "none",
+        }
+    }
+
+    /// Extension of the compressed tarball members, eg. `data.tar.gz`
+    fn extension(self) -> &'static str {
+        match self {
+            Self::Gz => ".gz",
+            Self::Xz => ".xz",
+            Self::None => "",
+        }
+    }
+
+    /// Value of `dpkg-deb -Z`
+    fn dpkg_name(self) -> &'static str {
+        match self {
+            Self::Gz => "gzip",
+            Self::Xz => "xz",
+            Self::None => "none",
+        }
+    }
+}
+
+/// How `--build` turns the staged tree into a `.deb`
+#[derive(Clone, Copy)]
+pub(crate) enum DebBuilder {
+    /// debforge's own archive writer, needs no external tools
+    Native(Compression),
+    /// `dpkg-deb --build`, from `--dpkg-deb`. `true` runs it under `fakeroot`, from `--fakeroot`
+    DpkgDeb(Compression, bool),
+}
+
+/// Options for the tarball members of a natively built `.deb`
+struct TarOptions {
+    /// Fixed mtime for every entry under `--reproducible`, otherwise each entry keeps its own
+    mtime: Option<u64>,
+    /// Whether this is the control member, whose scripts must be executable
+    control: bool,
+    /// The binary relative to the stage root when `--wrapper` moves it out of a `bin` directory
+    executable: Option<PathBuf>,
+}
+
+impl Variables {
+    /// Builds the staged tree of the current package into `{package}_{version}_{architecture}.deb` in the dist dir
+    pub(super) fn write_deb(&self, builder: DebBuilder) -> Result<PathBuf, Error> {
+        let stage_root = self.get_stage_root();
+        let control = fs::read_to_string(self.get_output_path(FileType::Control))?;
+        let deb = self.deb_path(&Control::parse(&control))?;
+        fs::create_dir_all(self.get_dist_dir())?;
+
+        match builder {
+            DebBuilder::Native(compression) => {
+                let mtime = self.reproducible.then(changelog::build_timestamp);
+                let executable = (self.wrapper && self.primary)
+                    .then(|| PathBuf::from(self.binary_path.trim_start_matches('/')));
+                write_native(&stage_root, &deb, compression, mtime, executable)?
+            }
+            DebBuilder::DpkgDeb(compression, fakeroot) => {
+                let output = tool::output(&mut self.dpkg_deb_command(compression, fakeroot, &deb))
+                    .map_err(|err| fatal!("failed to run dpkg-deb: {err}"))?;
+
+                if !output.status.success() {
+                    return Err(fatal!(
+                        "dpkg-deb failed to build: '{}'\n{}",
+                        deb.display(),
+                        String::from_utf8_lossy(&output.stderr).trim_end()
+                    ));
+                }
+            }
+        }
+        Ok(deb)
+    }
+
+    /// Where the current package is built to, named after the `Package`, `Version` and `Architecture` of `control`
+    pub(super) fn deb_path(&self, control: &Control) -> Result<PathBuf, Error> {
+        let field = |name: &str| {
+            control.get(name).ok_or_else(|| {
+                fatal!("control file is missing the '{name}' field, can not --build")
+            })
+        };
+        // The epoch is never part of the file name
+        let version = field("Version")?;
+        let version = version.split_once(':').map_or(version, |(_, rest)| rest);
+
+        Ok(self.get_dist_dir().join(format!(
+            "{}_{version}_{}.deb",
+            field("Package")?,
+            field("Architecture")?
+        )))
+    }
+
+    /// Under `fakeroot` files owned by the building user are recorded as owned by root, so `--root-owner-group` is
+    /// left out and ownership fakeroot reports is kept as is
+    pub(super) fn dpkg_deb_command(
+        &self,
+        compression: Compression,
+        fakeroot: bool,
+        deb: &Path,
+    ) -> Command {
+        let mut command = match fakeroot {
+            true => {
+                let mut command = Command::new("fakeroot");
+                command.arg("dpkg-deb").arg("--build");
+                command
+            }
+            false => {
+                let mut command = Command::new("dpkg-deb");
+                command.arg("--build").arg("--root-owner-group");
+                command
+            }
+        };
+        command
+            .arg(format!("-Z{}", compression.dpkg_name()))
+            .arg(self.get_stage_root())
+            .arg(deb);
+        command
+    }
+}
+
+/// Writes the `ar` archive dpkg expects, `debian-binary` followed by the control and data tarballs. Tarballs are
+/// built in temporary files next to the `.deb` so large binaries are streamed rather than held in memory
+fn write_native(
+    stage_root: &Path,
+    deb: &Path,
+    compression: Compression,
+    mtime: Option<u64>,
+    executable: Option<PathBuf>,
+) -> Result<(), Error> {
+    let control_name = format!("control.tar{}", compression.extension());
+    let data_name = format!("data.tar{}", compression.extension());
+    let control_tar = deb.with_extension(&control_name);
+    let data_tar = deb.with_extension(&data_name);
+
+    let control_dir = stage_root.join("DEBIAN");
+    write_tar(
+        &control_dir,
+        &control_dir,
+        &control_tar,
+        compression,
+        &TarOptions {
+            mtime,
+            control: true,
+            executable: None,
+        },
+    )?;
+    write_tar(
+        stage_root,
+        stage_root,
+        &data_tar,
+        compression,
+        &TarOptions {
+            mtime,
+            control: false,
+            executable,
+        },
+    )?;
+
+    let ar_mtime = mtime.unwrap_or_else(changelog::build_timestamp);
+    let mut out = BufWriter::new(fs::File::create(deb)?);
+    out.write_all(AR_MAGIC)?;
+    ar_member(
+        &mut out,
+        "debian-binary",
+        ar_mtime,
+        DEBIAN_BINARY.len() as u64,
+        &mut { DEBIAN_BINARY },
+    )?;
+    for (name, path) in [(&control_name, &control_tar), (&data_name, &data_tar)] {
+        let size = fs::metadata(path)?.len();
+        ar_member(
+            &mut out,
+            name,
+            ar_mtime,
+            size,
+            &mut BufReader::new(fs::File::open(path)?),
+        )?;
+        fs::remove_file(path)?;
+    }
+    Ok(out.flush()?)
+}
+
+/// Appends 1 member to an `ar` archive, padding its data to an even length
+fn ar_member(
+    out: &mut impl Write,
+    name: &str,
+    mtime: u64,
+    size: u64,
+    data: &mut impl io::Read,
+) -> io::Result<()> {
+    // The header ends with the 2 byte terminator "`\n"
+    writeln!(
+        out,
+        "{name:<16}{mtime:<12}{:<6}{:<6}{:<8}{size:<10}`",
+        0, 0, 100644
+    )?;
+    let copied = io::copy(data, out)?;
+    debug_assert_eq!(copied, size);
+    if size % 2 == 1 {
+        out.write_all(b"\n")?;
+    }
+    Ok(())
+}
+
+/// Writes the tree under `dir` as a compressed tarball of `./` relative paths, skipping `DEBIAN` in the data member
+fn write_tar(
+    root: &Path,
+    dir: &Path,
+    output: &Path,
+    compression: Compression,
+    options: &TarOptions,
+) -> Result<(), Error> {
+    let file = BufWriter::new(fs::File::create(output)?);
+    match compression {
+        Compression::Gz => {
+            let mut gz = GzBuilder::new();
+            if let Some(mtime) = options.mtime {
+                gz = gz.mtime(mtime as u32);
+            }
+            let mut builder = Builder::new(gz.write(file, GzLevel::best()));
+            append_tree(&mut builder, root, dir, options)?;
+            Ok(builder.into_inner()?.finish()?.flush()?)
+        }
+        Compression::None => {
+            let mut builder = Builder::new(file);
+            append_tree(&mut builder, root, dir, options)?;
+            Ok(builder.into_inner()?.flush()?)
+        }
+        Compression::Xz => {
+            // lzma-rs compresses from a reader, so the plain tarball is written first
+            let plain = output.with_extension("");
+            let mut builder = Builder::new(BufWriter::new(fs::File::create(&plain)?));
+            append_tree(&mut builder, root, dir, options)?;
+            builder.into_inner()?.flush()?;
+
+            let mut file = file;
+            lzma_rs::xz_compress(&mut BufReader::new(fs::File::open(&plain)?), &mut file)?;
+            fs::remove_file(plain)?;
+            Ok(file.flush()?)
+        }
+    }
+}
+
+/// Unix mode the entry is installed with. Modes are decided here rather than read from the host so packages built on
+/// hosts without unix permissions, eg. Windows, are still valid
+fn intended_mode(relative: &Path, metadata: &fs::Metadata, options: &TarOptions) -> u32 {
+    let name = relative.file_name().and_then(|name| name.to_str());
+    let parent = relative
+        .parent()
+        .and_then(Path::file_name)
+        .and_then(|name| name.to_str());
+
+    if options.control {
+        return if name.is_some_and(|name| SCRIPTS.contains(&name)) {
+            0o755
+        } else {
+            0o644
+        };
+    }
+    if matches!(parent, Some("bin" | "sbin" | "libexec"))
+        || options.executable.as_deref() == Some(relative)
+    {
+        return 0o755;
+    }
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        if metadata.permissions().mode() & 0o111 != 0 {
+            return 0o755;
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = metadata;
+
+    0o644
+}
+
+fn header(entry_type: EntryType, mode: u32, size: u64, mtime: u64) -> Header {
+    let mut header = Header::new_gnu();
+    header.set_entry_type(entry_type);
+    header.set_mode(mode);
+    header.set_size(size);
+    header.set_mtime(mtime);
+    header.set_uid(0);
+    header.set_gid(0);
+    let _ = header.set_username("root");
+    let _ = header.set_groupname("root");
+    header
+}
+
+/// `./` prefixed archive path joined with `/` regardless of the host's separator
+fn archive_path(relative: &Path) -> Result<String, Error> {
+    let mut archive_path = String::from(".");
+    for component in relative.components() {
+        let Some(name) = component.as_os_str().to_str() else {
+            return Err(fatal!(
+                "{PKG_NAME} can not package non UTF-8 path: '{}'",
+                relative.display()
+            ));
+        };
+        archive_path.push('/');
+        archive_path.push_str(name);
+    }
+    Ok(archive_path)
+}
+
+fn mtime(metadata: &fs::Metadata, options: &TarOptions) -> u64 {
+    options.mtime.unwrap_or_else(|| {
+        metadata
+            .modified()
+            .ok()
+            .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+            .map_or(0, |since| since.as_secs())
+    })
+}
+
+fn append_tree<W: Write>(
+    builder: &mut Builder<W>,
+    root: &Path,
+    dir: &Path,
+    options: &TarOptions,
+) -> Result<(), Error> {
+    if dir == root {
+        let mtime = mtime(&fs::metadata(root)?, options);
+        let mut header = header(EntryType::Directory, 0o755, 0, mtime);
+        builder.append_data(&mut header, "./", io::empty())?;
+    }
+
+    let mut entries = fs::read_dir(dir)?
+        .map(|entry| entry.map(|entry| (entry.path(), None)))
+        .collect::<io::Result<Vec<(PathBuf, Option<PathBuf>)>>>()?;
+    if !options.control
+        && let Some(deferred) = DEFERRED_LINKS.get()
+    {
+        let deferred = deferred.lock().expect("never poisoned");
+        entries.extend(
+            deferred
+                .iter()
+                .filter(|(link, _)| link.parent() == Some(dir))
+                .map(|(link, target)| (link.clone(), Some(target.clone()))),
+        );
+    }
+    entries.sort_by(|(a, _), (b, _)| a.file_name().cmp(&b.file_name()));
+
+    for (path, deferred_target) in entries {
+        if !options.control && dir == root && path.file_name() == Some("DEBIAN".as_ref()) {
+            continue;
+        }
+
+        let relative = path.strip_prefix(root).expect("entries are within `root`");
+        let archive_path = archive_path(relative)?;
+
+        if let Some(target) = deferred_target {
+            let mtime = options.mtime.unwrap_or_else(changelog::build_timestamp);
+            let mut header = header(EntryType::Symlink, 0o777, 0, mtime);
+            builder.append_link(&mut header, &archive_path, link_target(&target))?;
+            continue;
+        }
+
+        let metadata = fs::symlink_metadata(&path)?;
+        let file_type = metadata.file_type();
+        let mtime = mtime(&metadata, options);
+
+        if file_type.is_symlink() {
+            let mut header = header(EntryType::Symlink, 0o777, 0, mtime);
+            builder.append_link(&mut header, &archive_path, fs::read_link(&path)?)?;
+        } else if file_type.is_dir() {
+            let mut header = header(EntryType::Directory, 0o755, 0, mtime);
+            builder.append_data(&mut header, format!("{archive_path}/"), io::empty())?;
+            append_tree(builder, root, &path, options)?;
+        } else if file_type.is_file() {
+            let mode = intended_mode(relative, &metadata, options);
+            let mut header = header(EntryType::Regular, mode, metadata.len(), mtime);
+            // Copied from disk in chunks, a multi-hundred-MB binary is never buffered whole
+            builder.append_data(&mut header, &archive_path, fs::File::open(&path)?)?;
+        }
+    }
+    Ok(())
+}
+
+/// Link target joined with `/` regardless of the host's separator
+fn link_target(target: &Path) -> String {
+    let root = if target.has_root() { "/" } else { "" };
+    let components = target
+        .components()
+        .filter_map(|component| match component {
+            Component::Normal(name) => Some(name.to_string_lossy()),
+            Component::ParentDir => Some("..".into()),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    format!("{root}{}", components.join("/"))
+}
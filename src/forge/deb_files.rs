@@ -5,10 +5,28 @@ use std::{
 };
 
 use super::Variables;
+use crate::args::Architecture;
 
+pub(crate) const DEFAULT_STAGE_TEMPLATE: &str = "build/tmp/dist/linux/{name}-{version}";
+pub(crate) const STAGE_TEMPLATE_PLACEHOLDERS: [&str; 3] = ["{name}", "{version}", "{arch}"];
+pub(super) const BIN_DIR: &str = "usr/local/bin";
+/// Where system binaries, eg. daemons, are installed under `--sbin`
+const SBIN_DIR: &str = "usr/sbin";
+pub(super) const BINARY_COMPRESSIONS: [&str; 2] = ["gz", "xz"];
+/// Name of the Debian changelog when the project also provides its own changelog
+pub(super) const DEBIAN_CHANGELOG: &str = "changelog.Debian";
+const README_DEBIAN: &str = "README.Debian";
+/// Launcher installed in place of the binary under `--wrapper`
+const WRAPPER: &str = "wrapper.sh";
 const ICON_FORMATS: [&str; 5] = ["png", "jpg", "jpeg", "tiff", "svg"];
-const ICONS: [FileType; 4] = [
+const ICONS: [FileType; 10] = [
+    FileType::Icon16,
+    FileType::Icon22,
+    FileType::Icon24,
+    FileType::Icon32,
+    FileType::Icon48,
     FileType::Icon64,
+    FileType::Icon96,
     FileType::Icon128,
     FileType::Icon256,
     FileType::Icon512,
@@ -28,35 +46,104 @@ impl CowExt for Cow<'_, str> {
 }
 
 pub(super) trait DebParser {
-    fn debian_file(&self) -> Option<FileType>;
+    /// Classifies a file, also returning the `Architecture` it is restricted to when named like `postinst.arm64`
+    fn debian_file(&self) -> Option<(FileType, Option<Architecture>)>;
 }
 
 impl DebParser for &DirEntry {
-    fn debian_file(&self) -> Option<FileType> {
-        let file_name = self.file_name();
-        let name_str = file_name.to_str()?;
+    fn debian_file(&self) -> Option<(FileType, Option<Architecture>)> {
+        parse_file_name(self.file_name().to_str()?)
+    }
+}
 
-        if let Some((_, extension)) = name_str.rsplit_once('.') {
-            if extension == "desktop" {
-                return Some(FileType::Desktop);
-            }
+/// File names that carry a `.` without it starting an extension, eg. `changelog.Debian`
+fn compound_file_type(name: &str) -> Option<FileType> {
+    match name {
+        DEBIAN_CHANGELOG => Some(FileType::Changelog),
+        README_DEBIAN => Some(FileType::ReadmeDebian),
+        WRAPPER => Some(FileType::Wrapper),
+        "CHANGELOG" | "CHANGELOG.md" => Some(FileType::UpstreamChangelog),
+        _ => None,
+    }
+}
 
-            if ICON_FORMATS.contains(&extension) {
-                return ICONS
-                    .iter()
-                    .find(|&icon| name_str.contains(icon.width()))
-                    .copied();
-            }
+pub(super) fn parse_file_name(name_str: &str) -> Option<(FileType, Option<Architecture>)> {
+    if let Some(file_type) = compound_file_type(name_str) {
+        return Some((file_type, None));
+    }
+
+    if let Some((stem, extension)) = name_str.rsplit_once('.') {
+        if extension == "desktop" {
+            return Some((FileType::Desktop, None));
+        }
+
+        if ICON_FORMATS.contains(&extension) {
+            return ICONS
+                .iter()
+                .find(|&icon| contains_size_token(name_str, icon.width()))
+                .map(|&icon| (icon, None));
+        }
+
+        if let Some(architecture) = Architecture::from_short(extension) {
+            return compound_file_type(stem)
+                .or_else(|| FileType::from(stem))
+                .map(|file_type| (file_type, Some(architecture)));
+        }
+
+        return None;
+    }
+
+    FileType::from(name_str).map(|file_type| (file_type, None))
+}
+
+/// Matches `width` only when it is not part of a larger number, so `16` is found in `icon-16x16.png` but not in
+/// `icon-160.png`
+fn contains_size_token(name: &str, width: &str) -> bool {
+    name.match_indices(width).any(|(i, _)| {
+        let before = name[..i].chars().next_back();
+        let after = name[i + width.len()..].chars().next();
+        !before.is_some_and(|c| c.is_ascii_digit()) && !after.is_some_and(|c| c.is_ascii_digit())
+    })
+}
+
+/// Preset of the file types a kind of package is expected to ship on top of `REQUIRED_DEB_FILES`
+#[derive(Clone, Copy)]
+pub(crate) enum PackageType {
+    Cli,
+    Gui,
+    Library,
+}
+
+impl PackageType {
+    pub(crate) fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "cli" => Some(Self::Cli),
+            "gui" => Some(Self::Gui),
+            "library" => Some(Self::Library),
+            _ => None,
+        }
+    }
 
-            return None;
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            Self::Cli => "cli",
+            Self::Gui => "gui",
+            Self::Library => "library",
         }
+    }
 
-        FileType::from(name_str)
+    /// File types required in addition to `REQUIRED_DEB_FILES`
+    pub(crate) fn required(self) -> &'static [FileType] {
+        match self {
+            Self::Cli => &[],
+            Self::Gui => &[FileType::Desktop],
+            Self::Library => &[FileType::Symbols],
+        }
     }
 }
 
 #[derive(PartialEq, Eq, Hash, Clone, Copy, Debug)]
-pub(super) enum FileType {
+pub(crate) enum FileType {
     // required
     Control,
     Changelog,
@@ -64,7 +151,13 @@ pub(super) enum FileType {
     Binary,
 
     // optional
+    Icon16,
+    Icon22,
+    Icon24,
+    Icon32,
+    Icon48,
     Icon64,
+    Icon96,
     Icon128,
     Icon256,
     Icon512,
@@ -75,15 +168,71 @@ pub(super) enum FileType {
     PreRm,
     PostRm,
     ConfFiles,
+    Triggers,
+    Links,
     Watch,
     Format,
     Dirs,
     Docs,
     Menu,
     ManPages,
+    /// The project's own changelog, eg. `CHANGELOG.md`, installed next to the Debian changelog
+    UpstreamChangelog,
+    /// Notes on how the package differs from upstream, installed as `README.Debian`
+    ReadmeDebian,
+    /// Versioned symbols a shared library exports, for dpkg-shlibdeps of dependent packages
+    Symbols,
+    /// Launcher script that sets up the environment before running the binary, staged under `--wrapper`
+    Wrapper,
+    /// dpkg-gencontrol style `${name}` values, eg. `misc:Depends`, substituted into the control file rather than staged
+    Substvars,
 }
 
 impl FileType {
+    pub(crate) const ALL: [FileType; 34] = [
+        FileType::Control,
+        FileType::Changelog,
+        FileType::Copyright,
+        FileType::Binary,
+        FileType::Icon16,
+        FileType::Icon22,
+        FileType::Icon24,
+        FileType::Icon32,
+        FileType::Icon48,
+        FileType::Icon64,
+        FileType::Icon96,
+        FileType::Icon128,
+        FileType::Icon256,
+        FileType::Icon512,
+        FileType::Desktop,
+        FileType::Install,
+        FileType::PreInst,
+        FileType::PostInst,
+        FileType::PreRm,
+        FileType::PostRm,
+        FileType::ConfFiles,
+        FileType::Triggers,
+        FileType::Links,
+        FileType::Watch,
+        FileType::Format,
+        FileType::Dirs,
+        FileType::Docs,
+        FileType::Menu,
+        FileType::ManPages,
+        FileType::UpstreamChangelog,
+        FileType::ReadmeDebian,
+        FileType::Symbols,
+        FileType::Wrapper,
+        FileType::Substvars,
+    ];
+
+    /// Parses the user facing name of a file type as listed by `--list-filetypes`, ignoring case
+    pub(crate) fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|file_type| format!("{file_type:?}").eq_ignore_ascii_case(name))
+    }
+
     fn from(str: &str) -> Option<Self> {
         Some(match str {
             "control" => FileType::Control,
@@ -95,6 +244,8 @@ impl FileType {
             "prerm" => FileType::PreRm,
             "postrm" => FileType::PostRm,
             "conffiles" => FileType::ConfFiles,
+            "triggers" => FileType::Triggers,
+            "links" => FileType::Links,
             "watch" => FileType::Watch,
             "format" => FileType::Format,
             "dirs" => FileType::Dirs,
@@ -102,24 +253,67 @@ impl FileType {
             "desktop" => FileType::Desktop,
             "menu" => FileType::Menu,
             "manpages" => FileType::ManPages,
+            "symbols" => FileType::Symbols,
+            "substvars" => FileType::Substvars,
             _ => return None,
         })
     }
 
-    pub(super) fn is_text(self) -> bool {
-        !matches!(
+    /// The icon file type staged at `size`x`size`, eg. `256`
+    pub(crate) fn icon_for_size(size: &str) -> Option<Self> {
+        ICONS.into_iter().find(|icon| icon.width() == size)
+    }
+
+    pub(crate) fn icon_sizes() -> Vec<&'static str> {
+        ICONS.iter().map(|icon| icon.width()).collect()
+    }
+
+    pub(super) fn is_icon(self) -> bool {
+        ICONS.contains(&self)
+    }
+
+    /// Whether the file is installed on the target system rather than being package metadata under `DEBIAN`
+    pub(super) fn is_installed(self) -> bool {
+        self.is_icon()
+            || matches!(
+                self,
+                FileType::Binary
+                    | FileType::Wrapper
+                    | FileType::Desktop
+                    | FileType::Changelog
+                    | FileType::UpstreamChangelog
+                    | FileType::ReadmeDebian
+                    | FileType::Copyright
+            )
+    }
+
+    /// Docs staged gzip compressed, with a `.gz` suffix, once they are larger than `--compress-threshold`. The
+    /// copyright file is never compressed
+    pub(super) fn is_compressible(self) -> bool {
+        matches!(
             self,
-            FileType::Icon64
-                | FileType::Icon128
-                | FileType::Icon256
-                | FileType::Icon512
-                | FileType::Binary
+            FileType::Changelog | FileType::UpstreamChangelog | FileType::ReadmeDebian
         )
     }
 
-    fn width(self) -> &'static str {
+    /// Docs Debian policy 12.7 requires compressed whatever their size, `--compress-threshold` does not apply
+    pub(super) fn is_always_compressed(self) -> bool {
+        matches!(self, FileType::Changelog | FileType::UpstreamChangelog)
+    }
+
+    pub(crate) fn is_text(self) -> bool {
+        !(self.is_icon() || self == FileType::Binary)
+    }
+
+    pub(super) fn width(self) -> &'static str {
         match self {
+            FileType::Icon16 => "16",
+            FileType::Icon22 => "22",
+            FileType::Icon24 => "24",
+            FileType::Icon32 => "32",
+            FileType::Icon48 => "48",
             FileType::Icon64 => "64",
+            FileType::Icon96 => "96",
             FileType::Icon128 => "128",
             FileType::Icon256 => "256",
             FileType::Icon512 => "512",
@@ -127,9 +321,15 @@ impl FileType {
         }
     }
 
-    fn resolution(self) -> &'static str {
+    pub(super) fn resolution(self) -> &'static str {
         match self {
+            FileType::Icon16 => "16x16",
+            FileType::Icon22 => "22x22",
+            FileType::Icon24 => "24x24",
+            FileType::Icon32 => "32x32",
+            FileType::Icon48 => "48x48",
             FileType::Icon64 => "64x64",
+            FileType::Icon96 => "96x96",
             FileType::Icon128 => "128x128",
             FileType::Icon256 => "256x256",
             FileType::Icon512 => "512x512",
@@ -142,10 +342,17 @@ impl FileType {
             FileType::Control => Cow::Borrowed("control"),
             FileType::Changelog => Cow::Borrowed("changelog"),
             FileType::Copyright => Cow::Borrowed("copyright"),
-            FileType::Binary => Cow::Borrowed(linux_binary_name),
-            FileType::Icon64 | FileType::Icon128 | FileType::Icon256 | FileType::Icon512 => {
-                Cow::Owned(format!("{linux_binary_name}.png"))
-            }
+            FileType::Binary | FileType::Wrapper => Cow::Borrowed(linux_binary_name),
+            FileType::Icon16
+            | FileType::Icon22
+            | FileType::Icon24
+            | FileType::Icon32
+            | FileType::Icon48
+            | FileType::Icon64
+            | FileType::Icon96
+            | FileType::Icon128
+            | FileType::Icon256
+            | FileType::Icon512 => Cow::Owned(format!("{linux_binary_name}.png")),
             FileType::Desktop => Cow::Owned(format!("{linux_binary_name}.desktop")),
             FileType::Install => Cow::Borrowed("install"),
             FileType::PreInst => Cow::Borrowed("preinst"),
@@ -153,51 +360,233 @@ impl FileType {
             FileType::PreRm => Cow::Borrowed("prerm"),
             FileType::PostRm => Cow::Borrowed("postrm"),
             FileType::ConfFiles => Cow::Borrowed("conffiles"),
+            FileType::Triggers => Cow::Borrowed("triggers"),
+            FileType::Links => Cow::Borrowed("links"),
             FileType::Watch => Cow::Borrowed("watch"),
             FileType::Format => Cow::Borrowed("format"),
             FileType::Dirs => Cow::Borrowed("dirs"),
             FileType::Docs => Cow::Borrowed("docs"),
             FileType::Menu => Cow::Borrowed("menu"),
             FileType::ManPages => Cow::Borrowed("manpages"),
+            FileType::UpstreamChangelog => Cow::Borrowed("changelog"),
+            FileType::ReadmeDebian => Cow::Borrowed(README_DEBIAN),
+            FileType::Symbols => Cow::Borrowed("symbols"),
+            FileType::Substvars => Cow::Borrowed("substvars"),
         }
     }
 }
 
 impl Variables {
-    /// Binary source path
-    pub(super) fn get_binary_path(&self) -> PathBuf {
-        self.project_dir.join(format!(
-            "target\\{}\\release\\{}",
-            self.architecture.target(),
+    /// Binary source path, falls back to a `.gz` or `.xz` compressed binary when the plain binary does not exist
+    pub(super) fn get_binary_path(&self, architecture: Architecture) -> PathBuf {
+        let binary_path = self.project_dir.join(format!(
+            "target/{}/release/{}",
+            architecture.target(),
             self.binary_name
-        ))
+        ));
+
+        if binary_path.exists() {
+            return binary_path;
+        }
+
+        BINARY_COMPRESSIONS
+            .iter()
+            .map(|extension| {
+                let mut compressed = binary_path.clone().into_os_string();
+                compressed.push(format!(".{extension}"));
+                PathBuf::from(compressed)
+            })
+            .find(|compressed| compressed.exists())
+            .unwrap_or(binary_path)
+    }
+
+    /// Directory that staged packages and other build artifacts are written to
+    pub(super) fn get_dist_dir(&self) -> PathBuf {
+        let stage_root = self.get_stage_root();
+        stage_root
+            .parent()
+            .map_or_else(|| self.project_dir.clone(), Path::to_path_buf)
+    }
+
+    /// Root of the staged package tree, from `--dest-root`, `--stage-template` or `DEFAULT_STAGE_TEMPLATE`. When
+    /// staging more than 1 target with a template that does not include `{arch}` the architecture is appended to keep
+    /// the trees apart
+    pub(super) fn get_stage_root(&self) -> PathBuf {
+        if let Some(dest_root) = self.dest_root.as_ref() {
+            return dest_root.clone();
+        }
+
+        let template = self
+            .stage_template
+            .as_deref()
+            .unwrap_or(DEFAULT_STAGE_TEMPLATE);
+
+        let mut relative = template
+            .replace("{name}", &self.package_name)
+            .replace("{version}", &self.version)
+            .replace("{arch}", self.architecture.short());
+
+        if self.config.targets.len() > 1 && !template.contains("{arch}") {
+            relative.push_str(&format!("-{}", self.architecture.short()));
+        }
+
+        let stage_root = self.project_dir.join(relative);
+        if !self.partial {
+            return stage_root;
+        }
+        let name = stage_root
+            .file_name()
+            .expect("a stage template always names a directory")
+            .to_string_lossy();
+        stage_root.with_file_name(format!(".{name}.partial"))
+    }
+
+    /// Where the binary is installed without `--opt-layout`, or where it is linked from with it
+    pub(super) fn default_bin_dir(&self) -> &'static str {
+        if self.sbin { SBIN_DIR } else { BIN_DIR }
+    }
+
+    /// Install location of the package when using `--opt-layout`, eg. `opt/vendor/my-app`
+    pub(super) fn get_opt_dir(&self) -> Option<String> {
+        self.opt_vendor
+            .as_deref()
+            .map(|vendor| format!("opt/{vendor}/{}", self.linux_binary_name))
+    }
+
+    /// Where `file_type` is staged. A compressible doc resolves to its `.gz` path once it was staged compressed
+    pub(super) fn get_output_path(&self, file_type: FileType) -> PathBuf {
+        let out = self.get_uncompressed_output_path(file_type);
+        if file_type.is_compressible() {
+            let compressed = gz_path(&out);
+            if compressed.exists() {
+                return compressed;
+            }
+        }
+        out
+    }
+
+    /// Where `file_type` is staged when it is not compressed
+    pub(super) fn get_uncompressed_output_path(&self, file_type: FileType) -> PathBuf {
+        let mut out = self.get_file_type_path(file_type);
+        if file_type == FileType::Changelog && self.split_changelog {
+            out.push(DEBIAN_CHANGELOG);
+            return out;
+        }
+        out.push(
+            file_type
+                .output_file_name(&self.linux_binary_name)
+                .as_path(),
+        );
+        out
     }
 
     /// Output paths
     pub(super) fn get_file_type_path(&self, file_type: FileType) -> PathBuf {
-        let mut out = self.project_dir.join(format!(
-            "build\\tmp\\dist\\linux\\{}-{}",
-            self.linux_binary_name, self.version
-        ));
+        let mut out = self.get_stage_root();
 
-        match file_type {
-            FileType::Changelog | FileType::Copyright => {
-                out.push(format!("usr\\share\\doc\\{}", self.linux_binary_name))
+        if let Some(opt_dir) = self.get_opt_dir() {
+            match file_type {
+                FileType::Binary if self.wrapper => {
+                    out.push(format!("{opt_dir}/lib"));
+                    return out;
+                }
+                FileType::Binary | FileType::Wrapper => {
+                    out.push(format!("{opt_dir}/bin"));
+                    return out;
+                }
+                FileType::Changelog
+                | FileType::UpstreamChangelog
+                | FileType::ReadmeDebian
+                | FileType::Copyright => {
+                    out.push(format!("{opt_dir}/doc"));
+                    return out;
+                }
+                _ => (),
             }
-            icon @ (FileType::Icon64
-            | FileType::Icon128
-            | FileType::Icon256
-            | FileType::Icon512) => out.push(format!(
-                "usr\\share\\icons\\hicolor\\{}\\apps",
+        }
+
+        match file_type {
+            FileType::Changelog
+            | FileType::UpstreamChangelog
+            | FileType::ReadmeDebian
+            | FileType::Copyright => out.push(format!("usr/share/doc/{}", self.package_name)),
+            icon if icon.is_icon() => out.push(format!(
+                "usr/share/icons/hicolor/{}/apps",
                 icon.resolution()
             )),
-            FileType::Binary => {
-                out.push("usr\\local\\bin");
+            FileType::Binary if self.wrapper => {
+                out.push(format!("usr/lib/{}", self.linux_binary_name))
             }
-            FileType::Desktop => out.push("usr\\share\\applications"),
-            FileType::Format => out.push("DEBIAN\\source"),
+            FileType::Binary | FileType::Wrapper => {
+                out.push(self.default_bin_dir());
+            }
+            FileType::Desktop => out.push("usr/share/applications"),
+            FileType::Format => out.push("DEBIAN/source"),
             _ => out.push("DEBIAN"),
         }
         out
     }
 }
+
+/// `path` with a `.gz` suffix appended, eg. `changelog.Debian.gz`
+pub(super) fn gz_path(path: &Path) -> PathBuf {
+    let mut compressed = path.as_os_str().to_owned();
+    compressed.push(".gz");
+    PathBuf::from(compressed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compound_names() {
+        assert_eq!(
+            parse_file_name("changelog.Debian"),
+            Some((FileType::Changelog, None))
+        );
+        assert_eq!(
+            parse_file_name("README.Debian"),
+            Some((FileType::ReadmeDebian, None))
+        );
+        assert_eq!(
+            parse_file_name("CHANGELOG.md"),
+            Some((FileType::UpstreamChangelog, None))
+        );
+        assert_eq!(
+            parse_file_name("wrapper.sh"),
+            Some((FileType::Wrapper, None))
+        );
+    }
+
+    #[test]
+    fn compound_names_with_architecture() {
+        assert_eq!(
+            parse_file_name("changelog.Debian.arm64"),
+            Some((FileType::Changelog, Some(Architecture::Arm64)))
+        );
+        assert_eq!(
+            parse_file_name("README.Debian.amd64"),
+            Some((FileType::ReadmeDebian, Some(Architecture::Amd64)))
+        );
+        assert_eq!(
+            parse_file_name("control.amd64"),
+            Some((FileType::Control, Some(Architecture::Amd64)))
+        );
+    }
+
+    #[test]
+    fn several_dots() {
+        assert_eq!(
+            parse_file_name("my.app.desktop"),
+            Some((FileType::Desktop, None))
+        );
+        assert_eq!(
+            parse_file_name("my.app-48x48.png"),
+            Some((FileType::Icon48, None))
+        );
+        assert_eq!(parse_file_name("changelog.Debian.gz"), None);
+        assert_eq!(parse_file_name("control.old.amd64"), None);
+        assert_eq!(parse_file_name("README.md"), None);
+    }
+}
@@ -0,0 +1,115 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use super::{FileType, Variables, error::Error};
+use crate::strict_warn;
+
+/// The locale of a translated desktop entry fragment named like `app.fr.desktop` or `app.pt_BR.desktop`
+pub(super) fn locale(file_name: &str) -> Option<&str> {
+    let (_, locale) = file_name.strip_suffix(".desktop")?.rsplit_once('.')?;
+    let (language, rest) = locale
+        .find(['_', '@'])
+        .map_or((locale, ""), |i| locale.split_at(i));
+    let (country, modifier) = rest.split_once('@').unwrap_or((rest, ""));
+
+    let valid = (2..=3).contains(&language.len())
+        && language.bytes().all(|b| b.is_ascii_lowercase())
+        && (country.is_empty()
+            || country.len() == 3 && country[1..].bytes().all(|b| b.is_ascii_uppercase()))
+        && modifier.bytes().all(|b| b.is_ascii_alphanumeric());
+    valid.then_some(locale)
+}
+
+/// Appends each key of `fragment` to the end of the same `[Group]` of `base`, returning a problem for every key that
+/// could not be merged. A key `base` already declares, eg. an untranslated `Name`, is never duplicated
+fn merge_keys(base: &mut Vec<String>, fragment: &str) -> Vec<String> {
+    let mut problems = Vec::new();
+    let mut group = None;
+
+    for line in fragment.lines().map(str::trim) {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if line.starts_with('[') {
+            group = Some(line);
+            continue;
+        }
+        let Some((key, _)) = line.split_once('=') else {
+            continue;
+        };
+        let key = key.trim();
+
+        let Some(group) = group else {
+            problems.push(format!("key: '{key}' is outside of any group"));
+            continue;
+        };
+        let Some(start) = base.iter().position(|base_line| base_line.trim() == group) else {
+            problems.push(format!(
+                "key: '{key}' belongs to {group}, which the main desktop entry does not declare"
+            ));
+            continue;
+        };
+        let end = base[start + 1..]
+            .iter()
+            .position(|base_line| base_line.trim_start().starts_with('['))
+            .map_or(base.len(), |i| start + 1 + i);
+
+        let declared = base[start + 1..end].iter().any(|base_line| {
+            base_line
+                .split_once('=')
+                .is_some_and(|(base_key, _)| base_key.trim() == key)
+        });
+        if declared {
+            let kind = if key.contains('[') {
+                "key"
+            } else {
+                "untranslated key"
+            };
+            problems.push(format!(
+                "{kind}: '{key}' in {group} duplicates the main desktop entry"
+            ));
+            continue;
+        }
+
+        // Keep the group's trailing blank lines after the merged keys
+        let insert_at = (start + 1..end)
+            .rev()
+            .find(|&i| !base[i].trim().is_empty())
+            .map_or(start + 1, |i| i + 1);
+        base.insert(insert_at, line.to_string());
+    }
+    problems
+}
+
+impl Variables {
+    /// Merges the localized keys of each `<name>.<locale>.desktop` fragment into the staged desktop entry rendered
+    /// from `base`
+    pub(super) fn write_merged_desktop(
+        &self,
+        base: &Path,
+        fragments: &[PathBuf],
+    ) -> Result<(), Error> {
+        let mut merged = self
+            .render_text(FileType::Desktop, base)?
+            .lines()
+            .map(String::from)
+            .collect::<Vec<_>>();
+
+        for fragment in fragments {
+            let contents = self.render_text(FileType::Desktop, fragment)?;
+            for problem in merge_keys(&mut merged, &contents) {
+                strict_warn!(self.strict, "{problem} in: '{}'", fragment.display())?
+            }
+        }
+
+        let mut contents = merged.join("\n");
+        contents.push('\n');
+        fs::create_dir_all(self.get_file_type_path(FileType::Desktop))?;
+        Ok(fs::write(
+            self.get_output_path(FileType::Desktop),
+            contents,
+        )?)
+    }
+}
@@ -0,0 +1,379 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use super::{
+    DebFiles, FileType, Package, SEARCH_DIRS, SearchDir, Variables, copyright, error::Error, hash,
+    user_install,
+};
+use crate::{args::Architecture, fatal, warn};
+
+/// Command line input that decides where files are discovered
+pub(super) struct Discovery {
+    pub(super) binary_sha256: Option<String>,
+    /// Control files of the packages staged from the project, empty stages a single package
+    pub(super) package_controls: Vec<PathBuf>,
+    pub(super) prefer_dir: Option<SearchDir>,
+    pub(super) locale_desktop: bool,
+    pub(super) search_dirs: Vec<PathBuf>,
+    /// debhelper style `debian/` directory whose files replace those found by the default scan
+    pub(super) debian_dir: Option<PathBuf>,
+    pub(super) package_name_given: bool,
+    pub(super) dry_run: bool,
+}
+
+/// Everything found for a run, before any of it is validated or staged
+pub(super) struct Discovered {
+    /// The project binary of each target
+    pub(super) binaries: Vec<(Architecture, PathBuf)>,
+    /// The primary package followed by any `--package`
+    pub(super) packages: Vec<Package>,
+    /// Targets whose binary was not found, with the directory it was looked for in
+    pub(super) missing: Vec<(Architecture, String)>,
+    /// Discovery input that could not be used, eg. a `--search-dir` that is not a directory. Discovery carries on
+    /// without it so `--list-found` can list everything else
+    pub(super) errors: Vec<String>,
+}
+
+impl Variables {
+    /// Locates each target's binary and scans the project for the files of every package. Nothing is written
+    pub(super) fn discover(&mut self, discovery: Discovery) -> Result<Discovered, Error> {
+        let mut deb_files = DebFiles::new(
+            discovery.prefer_dir,
+            &self.config.icons,
+            discovery.locale_desktop,
+        );
+        let mut binaries = Vec::with_capacity(self.config.targets.len());
+        let (mut missing, mut errors) = (Vec::new(), Vec::new());
+
+        // Each target's binary is resolved from its own `target/<triple>/release` directory, every target is checked
+        // before reporting the ones that are missing
+        for &architecture in self.config.targets.iter() {
+            let binary_path = self.get_binary_path(architecture);
+            if !binary_path.exists() {
+                let dir = binary_path
+                    .parent()
+                    .expect("`get_binary_path` gives a location within a directory")
+                    .display()
+                    .to_string();
+                missing.push((architecture, dir));
+                continue;
+            }
+
+            if discovery.dry_run {
+                println!("Found Binary file for {}", architecture.short())
+            }
+            binaries.push((architecture, binary_path));
+        }
+        // A missing binary is reported instead, there is nothing to verify
+        if let Some(expected) = discovery.binary_sha256
+            && missing.is_empty()
+        {
+            let [(_, ref binary_path)] = binaries[..] else {
+                return Err(fatal!(
+                    "--binary-sha256 can only verify a single target's binary"
+                ));
+            };
+
+            let computed = hash::sha256_file(binary_path)?;
+            if computed != expected {
+                return Err(fatal!(
+                    "Binary sha256 mismatch for: '{}'\n  expected: {expected}\n  computed: {computed}",
+                    binary_path.display()
+                ));
+            }
+            if discovery.dry_run {
+                println!("Verified Binary sha256")
+            }
+        }
+
+        let mut package_dirs = Vec::with_capacity(discovery.package_controls.len());
+        for control in discovery.package_controls.iter() {
+            let control = self.project_dir.join(control);
+            if !control.is_file() {
+                errors.push(format!(
+                    "--package control file: '{}' does not exist",
+                    control.display()
+                ));
+                continue;
+            }
+            let dir = control
+                .parent()
+                .expect("a file is always within a directory")
+                .to_path_buf();
+            if dir == self.project_dir {
+                errors.push(String::from(
+                    "--package control files must be in a subdirectory of the project",
+                ));
+                continue;
+            }
+            package_dirs.push(dir);
+        }
+
+        let debian_dir = discovery.debian_dir.and_then(|dir| {
+            let dir = self.project_dir.join(dir);
+            if !dir.is_dir() {
+                errors.push(format!(
+                    "--debian-dir: '{}' is not a directory",
+                    dir.display()
+                ));
+                return None;
+            }
+            Some(dir)
+        });
+        let skip = package_dirs
+            .iter()
+            .chain(debian_dir.as_ref())
+            .cloned()
+            .collect::<Vec<_>>();
+
+        for entry in fs::read_dir(&self.project_dir)? {
+            let entry = entry?;
+            let file_type = entry.file_type()?;
+
+            if file_type.is_dir() && debian_dir.as_ref() == Some(&entry.path()) {
+                continue;
+            }
+            if file_type.is_dir() {
+                let file_name = entry.file_name();
+                if let Some(search_dir) = SEARCH_DIRS.iter().find(|&&valid| file_name == valid) {
+                    search_dir.scan(
+                        entry.path(),
+                        *search_dir,
+                        &mut deb_files,
+                        &skip,
+                        discovery.dry_run,
+                    )?;
+                }
+            } else if file_type.is_file() {
+                deb_files.conditional_insert(&entry, None, discovery.dry_run)?
+            }
+        }
+
+        // Extra directories are searched like `assets` and share its origin for `--prefer-dir`
+        for dir in discovery.search_dirs {
+            let dir = self.project_dir.join(dir);
+            if !dir.is_dir() {
+                errors.push(format!(
+                    "--search-dir: '{}' is not a directory",
+                    dir.display()
+                ));
+                continue;
+            }
+            if discovery.dry_run {
+                println!("Scanning: '{}'", dir.display())
+            }
+            SearchDir::Assets.scan(
+                dir,
+                SearchDir::Assets,
+                &mut deb_files,
+                &skip,
+                discovery.dry_run,
+            )?;
+        }
+
+        if let Some(dir) = debian_dir {
+            let mut authoritative = DebFiles::new(
+                discovery.prefer_dir,
+                &self.config.icons,
+                discovery.locale_desktop,
+            );
+            authoritative.package_prefix = Some(self.package_name.clone());
+            if discovery.dry_run {
+                println!("Scanning debian directory: '{}'", dir.display())
+            }
+            scan_debian_dir(&dir, &mut authoritative, &package_dirs, discovery.dry_run)?;
+            deb_files.overlay(authoritative, discovery.dry_run);
+        }
+
+        let mut packages = Vec::with_capacity(package_dirs.len().max(1));
+        for dir in package_dirs.iter() {
+            let mut files = DebFiles::new(
+                discovery.prefer_dir,
+                &self.config.icons,
+                discovery.locale_desktop,
+            );
+            if discovery.dry_run {
+                println!("Scanning package: '{}'", dir.display())
+            }
+            SearchDir::Debian.scan(
+                dir,
+                SearchDir::Debian,
+                &mut files,
+                &package_dirs,
+                discovery.dry_run,
+            )?;
+
+            if packages.is_empty() {
+                // Files found outside of every package directory belong to the primary package
+                if let Err(duplicate) = files.absorb(std::mem::take(&mut deb_files)) {
+                    errors.push(format!(
+                        "found more than 1 {duplicate} for package: '{}'",
+                        dir.display()
+                    ));
+                }
+            }
+
+            let name = self.read_package_name(&files)?;
+            packages.push(Package {
+                name,
+                files,
+                doc_link: None,
+            });
+        }
+
+        if packages.is_empty() {
+            // Docs are staged under the derived name, a control file naming another package would not find them
+            if !discovery.package_name_given && deb_files.contains(FileType::Control) {
+                let (_, control) = self.read_control(&deb_files)?;
+                if let Some(control_package) = control
+                    .get("Package")
+                    .filter(|&control_package| control_package != self.package_name)
+                {
+                    warn!(
+                        "control Package: '{control_package}' differs from: '{}' derived from the binary name, \
+                        supply --package-name {control_package} to stage for it instead",
+                        self.package_name
+                    )
+                }
+            }
+            packages.push(Package {
+                name: self.package_name.clone(),
+                files: deb_files,
+                doc_link: None,
+            });
+        }
+
+        Ok(Discovered {
+            binaries,
+            packages,
+            missing,
+            errors,
+        })
+    }
+
+    /// Errors on any missing binaries, reported together, otherwise on the first unusable discovery input. Every run
+    /// but `--list-found` ends on them
+    pub(super) fn check_discovered(&self, discovered: &Discovered) -> Result<(), Error> {
+        match discovered.missing.as_slice() {
+            [] => (),
+            [(_, dir)] if self.config.targets.len() == 1 => {
+                return Err(fatal!(
+                    "failed to find Binary: '{}' at: '{dir}'",
+                    self.binary_name
+                ));
+            }
+            missing => {
+                return Err(fatal!(
+                    "failed to find Binary: '{}' for {} of {} targets:\n    {}",
+                    self.binary_name,
+                    missing.len(),
+                    self.config.targets.len(),
+                    missing
+                        .iter()
+                        .map(|(architecture, dir)| format!("{}: '{dir}'", architecture.short()))
+                        .collect::<Vec<_>>()
+                        .join("\n    ")
+                ));
+            }
+        }
+
+        match discovered.errors.first() {
+            Some(error) => Err(fatal!("{error}")),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Scans the top level of a debhelper style `debian/` directory. Of its subdirectories only `copyright.d` and the
+/// `--user-install` templates are read, the rest, eg. `source/` or debhelper's own staging trees, are skipped
+fn scan_debian_dir(
+    dir: &Path,
+    deb_files: &mut DebFiles,
+    skip: &[PathBuf],
+    dry_run: bool,
+) -> Result<(), Error> {
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let file_type = entry.file_type()?;
+
+        if file_type.is_file() {
+            deb_files.conditional_insert(&entry, Some(SearchDir::Debian), dry_run)?;
+            continue;
+        }
+        if !file_type.is_dir() || skip.contains(&entry.path()) {
+            continue;
+        }
+        if entry.file_name() == copyright::FRAGMENT_DIR {
+            deb_files.insert_copyright_fragments(&entry.path(), dry_run)?
+        } else if entry.file_name() == user_install::TEMPLATE_DIR {
+            if dry_run {
+                println!("Found user templates in: '{}'", entry.path().display())
+            }
+            deb_files.user_templates = Some(entry.path())
+        } else if dry_run {
+            println!("Skipped directory: '{}'", entry.path().display())
+        }
+    }
+    Ok(())
+}
+
+/// Prints every discovered file with the file type it was matched as, for `--list-found`, followed by what could not be
+/// found. Paths within `project_dir` are shown relative to it
+pub(super) fn list(discovered: &Discovered, project_dir: &Path) {
+    let display = |path: &Path| {
+        path.strip_prefix(project_dir)
+            .unwrap_or(path)
+            .display()
+            .to_string()
+    };
+
+    for (i, package) in discovered.packages.iter().enumerate() {
+        let mut rows = Vec::new();
+        if i == 0 {
+            for (architecture, path) in discovered.binaries.iter() {
+                rows.push((format!("Binary ({})", architecture.short()), display(path)));
+            }
+            for (architecture, dir) in discovered.missing.iter() {
+                rows.push((
+                    format!("Binary ({})", architecture.short()),
+                    format!("missing, looked in: {}", display(Path::new(dir))),
+                ));
+            }
+        }
+        let files = &package.files;
+        for (&file_type, path) in files.files.iter() {
+            rows.push((format!("{file_type:?}"), display(path)));
+        }
+        for (&(file_type, architecture), path) in files.arch_files.iter() {
+            rows.push((
+                format!("{file_type:?} ({})", architecture.short()),
+                display(path),
+            ));
+        }
+        for path in files.copyright_fragments.iter() {
+            rows.push((String::from("Copyright fragment"), display(path)));
+        }
+        for path in files.desktop_locales.iter() {
+            rows.push((String::from("Desktop translation"), display(path)));
+        }
+        if let Some(path) = files.user_templates.as_deref() {
+            rows.push((String::from("User templates"), display(path)));
+        }
+        rows.sort();
+
+        println!("Found files for package: '{}'", package.name);
+        let width = rows.iter().map(|(kind, _)| kind.len()).max().unwrap_or(0);
+        for (kind, path) in rows {
+            println!("    {kind:<width$}  {path}")
+        }
+    }
+
+    if !discovered.errors.is_empty() {
+        println!("Could not use:");
+        for error in discovered.errors.iter() {
+            println!("    {error}")
+        }
+    }
+}
@@ -0,0 +1,185 @@
+use std::io;
+
+use super::{PKG_NAME, Variables, config::Config};
+use crate::args::Args;
+
+/// Name of the layer a setting comes from when no layer sets it
+const DEFAULT: &str = "default";
+
+/// Name of the highest precedence layer that sets the setting `is_set` checks for
+fn source(layers: &[(&'static str, Config)], is_set: impl Fn(&Config) -> bool) -> &'static str {
+    layers
+        .iter()
+        .find(|(_, config)| is_set(config))
+        .map_or(DEFAULT, |&(name, _)| name)
+}
+
+/// Placeholder printed for settings without a value
+fn or_dash(value: &str) -> &str {
+    if value.is_empty() { "-" } else { value }
+}
+
+fn row(name: &str, value: impl AsRef<str>, source: &str) {
+    println!("    {name:<16} {:<40} ({source})", or_dash(value.as_ref()))
+}
+
+/// Prints every effective setting along with the layer it was resolved from, for `--dump-config`
+pub(super) fn print(mut args: Args) -> io::Result<()> {
+    let binary_source = if args.binary_name.is_some() {
+        "command line"
+    } else {
+        "Cargo.toml"
+    };
+    let package_source = if args.package_name.is_some() {
+        "command line"
+    } else {
+        "binary name"
+    };
+    // Same order `Variables::from_layers` and `Forge::from` resolve the version in, the changelog is only read once
+    // the debian directory is discovered so its version is left as a dash
+    let version_from_changelog = args.version_from_changelog;
+    let version_source = match (args.version.is_some(), args.version_suffix.is_some()) {
+        _ if version_from_changelog => "changelog",
+        (true, false) => "command line",
+        (false, false) => "Cargo.toml",
+        (true, true) => "command line, with --version-suffix",
+        (false, true) => "Cargo.toml, with --version-suffix",
+    };
+    let flags = args.flags();
+
+    let layers = args.config_layers()?;
+    let mut vars = Variables::from_layers(args, &layers);
+    let config = &vars.config;
+    let optional = |value: Option<&str>| value.unwrap_or_default().to_string();
+
+    println!("{PKG_NAME}: Effective configuration");
+    row("binary name", &vars.binary_name, binary_source);
+    let version = if version_from_changelog {
+        ""
+    } else {
+        &vars.version
+    };
+    row("version", version, version_source);
+    row("package name", &vars.package_name, package_source);
+    row(
+        "targets",
+        config
+            .targets
+            .iter()
+            .map(|target| target.short())
+            .collect::<Vec<_>>()
+            .join(", "),
+        source(&layers, |layer| !layer.targets.is_empty()),
+    );
+    row(
+        "supported archs",
+        config
+            .supported_architectures
+            .iter()
+            .map(|architecture| architecture.short())
+            .collect::<Vec<_>>()
+            .join(", "),
+        source(&layers, |layer| !layer.supported_architectures.is_empty()),
+    );
+    row(
+        "maintainer",
+        optional(config.maintainer.as_deref()),
+        source(&layers, |layer| layer.maintainer.is_some()),
+    );
+    row(
+        "section",
+        optional(config.section.as_deref()),
+        source(&layers, |layer| layer.section.is_some()),
+    );
+    row(
+        "homepage",
+        optional(config.homepage.as_deref()),
+        source(&layers, |layer| layer.homepage.is_some()),
+    );
+    row(
+        "distribution",
+        config.distribution(),
+        source(&layers, |layer| layer.distribution.is_some()),
+    );
+    row(
+        "depends",
+        config.depends.join(", "),
+        source(&layers, |layer| !layer.depends.is_empty()),
+    );
+    row(
+        "pre-depends",
+        config.pre_depends.join(", "),
+        source(&layers, |layer| !layer.pre_depends.is_empty()),
+    );
+    row(
+        "conflicts",
+        config.conflicts.join(", "),
+        source(&layers, |layer| !layer.conflicts.is_empty()),
+    );
+    row(
+        "replaces",
+        config.replaces.join(", "),
+        source(&layers, |layer| !layer.replaces.is_empty()),
+    );
+    row(
+        "provides",
+        config.provides.join(", "),
+        source(&layers, |layer| !layer.provides.is_empty()),
+    );
+    row(
+        "license",
+        optional(config.license.as_deref()),
+        source(&layers, |layer| layer.license.is_some()),
+    );
+    row(
+        "authors",
+        config.authors.join(", "),
+        source(&layers, |layer| !layer.authors.is_empty()),
+    );
+    row(
+        "icons",
+        config
+            .icons
+            .iter()
+            .map(|(file_name, icon)| format!("{file_name} = {icon:?}"))
+            .collect::<Vec<_>>()
+            .join(", "),
+        source(&layers, |layer| !layer.icons.is_empty()),
+    );
+
+    row(
+        "multi-arch",
+        optional(config.multi_arch.as_deref()),
+        source(&layers, |layer| layer.multi_arch.is_some()),
+    );
+    row(
+        "essential",
+        config
+            .essential
+            .map(|essential| essential.to_string())
+            .unwrap_or_default(),
+        source(&layers, |layer| layer.essential.is_some()),
+    );
+
+    println!("Output:");
+    for &architecture in vars.config.targets.clone().iter() {
+        vars.architecture = architecture;
+        println!(
+            "    {:<16} {}",
+            architecture.short(),
+            vars.get_stage_root().display()
+        );
+    }
+
+    vars.architecture = vars.config.targets[0];
+    println!("Replacements:");
+    for (key, value) in vars.replacements() {
+        println!("    {key:<16} {value}")
+    }
+
+    println!("Flags:");
+    for (flag, value) in flags {
+        println!("    {flag:<24} {}", or_dash(&value))
+    }
+    Ok(())
+}
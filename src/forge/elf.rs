@@ -0,0 +1,241 @@
+use std::{
+    fs,
+    io::{self, BufRead, Read, Seek, SeekFrom},
+    path::Path,
+};
+
+const ELF_MAGIC: [u8; 4] = *b"\x7fELF";
+
+pub(super) fn is_elf(path: &Path) -> io::Result<bool> {
+    let mut magic = [0; ELF_MAGIC.len()];
+    match fs::File::open(path)?.read_exact(&mut magic) {
+        Ok(()) => Ok(magic == ELF_MAGIC),
+        Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => Ok(false),
+        Err(err) => Err(err),
+    }
+}
+
+const SHT_DYNAMIC: u32 = 6;
+const SHT_NOTE: u32 = 7;
+const NT_GNU_BUILD_ID: u64 = 3;
+const DT_NULL: u64 = 0;
+const DT_NEEDED: u64 = 1;
+
+/// Reads the fixed width fields of an ELF file in its class and byte order
+struct ElfReader {
+    file: fs::File,
+    /// File length in bytes, every offset read from the file is checked against it
+    len: u64,
+    is_64: bool,
+    little_endian: bool,
+}
+
+/// Error for an offset or size that points outside of the file
+fn malformed() -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, "malformed ELF file")
+}
+
+impl ElfReader {
+    /// End of the `size` bytes at `offset`, erroring when they overflow or run past the end of the file
+    fn range(&self, offset: u64, size: u64) -> io::Result<u64> {
+        offset
+            .checked_add(size)
+            .filter(|&end| end <= self.len)
+            .ok_or_else(malformed)
+    }
+
+    fn bytes<const N: usize>(&mut self, offset: u64) -> io::Result<[u8; N]> {
+        let mut buf = [0; N];
+        self.file.seek(SeekFrom::Start(offset))?;
+        self.file.read_exact(&mut buf)?;
+        Ok(buf)
+    }
+
+    fn u16(&mut self, offset: u64) -> io::Result<u64> {
+        let bytes = self.bytes(offset)?;
+        Ok(if self.little_endian {
+            u16::from_le_bytes(bytes)
+        } else {
+            u16::from_be_bytes(bytes)
+        } as u64)
+    }
+
+    fn u32(&mut self, offset: u64) -> io::Result<u64> {
+        let bytes = self.bytes(offset)?;
+        Ok(if self.little_endian {
+            u32::from_le_bytes(bytes)
+        } else {
+            u32::from_be_bytes(bytes)
+        } as u64)
+    }
+
+    fn u64(&mut self, offset: u64) -> io::Result<u64> {
+        let bytes = self.bytes(offset)?;
+        Ok(if self.little_endian {
+            u64::from_le_bytes(bytes)
+        } else {
+            u64::from_be_bytes(bytes)
+        })
+    }
+
+    /// A word sized field, 8 bytes in 64 bit files and 4 in 32 bit files
+    fn word(&mut self, offset: u64) -> io::Result<u64> {
+        if self.is_64 {
+            self.u64(offset)
+        } else {
+            self.u32(offset)
+        }
+    }
+
+    fn c_str(&mut self, offset: u64, end: u64) -> io::Result<String> {
+        self.file.seek(SeekFrom::Start(offset))?;
+        let mut out = Vec::new();
+        io::BufReader::new((&mut self.file).take(end.saturating_sub(offset)))
+            .read_until(0, &mut out)?;
+        if out.last() == Some(&0) {
+            out.pop();
+        }
+        Ok(String::from_utf8_lossy(&out).into_owned())
+    }
+}
+
+/// The fields of a section header this module reads
+struct Section {
+    kind: u32,
+    offset: u64,
+    size: u64,
+    link: u64,
+}
+
+impl ElfReader {
+    fn open(path: &Path) -> io::Result<Self> {
+        let mut file = fs::File::open(path)?;
+        let mut ident = [0; 16];
+        file.read_exact(&mut ident)?;
+        if ident[..ELF_MAGIC.len()] != ELF_MAGIC {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "not an ELF file",
+            ));
+        }
+
+        Ok(Self {
+            len: file.metadata()?.len(),
+            file,
+            is_64: ident[4] == 2,
+            little_endian: ident[5] == 1,
+        })
+    }
+
+    fn sections(&mut self) -> io::Result<Vec<Section>> {
+        let (shoff, shentsize, shnum) = if self.is_64 {
+            (self.u64(0x28)?, self.u16(0x3a)?, self.u16(0x3c)?)
+        } else {
+            (self.u32(0x20)?, self.u16(0x2e)?, self.u16(0x30)?)
+        };
+
+        (0..shnum)
+            .map(|index| {
+                let header = index
+                    .checked_mul(shentsize)
+                    .and_then(|offset| shoff.checked_add(offset))
+                    .ok_or_else(malformed)?;
+                self.range(header, shentsize)?;
+                Ok(if self.is_64 {
+                    Section {
+                        kind: self.u32(header + 4)? as u32,
+                        offset: self.u64(header + 0x18)?,
+                        size: self.u64(header + 0x20)?,
+                        link: self.u32(header + 0x28)?,
+                    }
+                } else {
+                    Section {
+                        kind: self.u32(header + 4)? as u32,
+                        offset: self.u32(header + 0x10)?,
+                        size: self.u32(header + 0x14)?,
+                        link: self.u32(header + 0x18)?,
+                    }
+                })
+            })
+            .collect()
+    }
+}
+
+/// Shared libraries the ELF file at `path` declares as `NEEDED` in its `.dynamic` section, read directly rather
+/// than by running `ldd` on an untrusted binary. Empty for statically linked binaries
+pub(super) fn needed_libraries(path: &Path) -> io::Result<Vec<String>> {
+    let mut elf = ElfReader::open(path)?;
+    let sections = elf.sections()?;
+
+    let mut needed = Vec::new();
+    for section in sections
+        .iter()
+        .filter(|section| section.kind == SHT_DYNAMIC)
+    {
+        // `sh_link` of the dynamic section is its string table
+        let Some(strtab) = sections.get(section.link as usize) else {
+            continue;
+        };
+        let end = elf.range(section.offset, section.size)?;
+        let strtab_end = elf.range(strtab.offset, strtab.size)?;
+        let entry_size = if elf.is_64 { 16 } else { 8 };
+        for entry in (section.offset..end).step_by(entry_size) {
+            match elf.word(entry)? {
+                DT_NULL => break,
+                DT_NEEDED => {
+                    let name = elf.word(entry + entry_size as u64 / 2)?;
+                    let name = strtab
+                        .offset
+                        .checked_add(name)
+                        .filter(|&name| name < strtab_end)
+                        .ok_or_else(malformed)?;
+                    needed.push(elf.c_str(name, strtab_end)?)
+                }
+                _ => (),
+            }
+        }
+    }
+    Ok(needed)
+}
+
+/// Hex encoded GNU build-id of the ELF file at `path`, read from its `.note.gnu.build-id` section. `None` when the
+/// binary was linked without one
+pub(super) fn build_id(path: &Path) -> io::Result<Option<String>> {
+    let mut elf = ElfReader::open(path)?;
+
+    for section in elf
+        .sections()?
+        .iter()
+        .filter(|section| section.kind == SHT_NOTE)
+    {
+        let mut note = section.offset;
+        let end = elf.range(section.offset, section.size)?;
+        // Each note is a name size, description size and type followed by the padded name and description
+        while note + 12 <= end {
+            let (name_size, desc_size, kind) =
+                (elf.u32(note)?, elf.u32(note + 4)?, elf.u32(note + 8)?);
+            let name = note + 12;
+            let desc = name
+                .checked_add(name_size.next_multiple_of(4))
+                .ok_or_else(malformed)?;
+            // The description must lie within the note section before its claimed size is allocated
+            if desc
+                .checked_add(desc_size)
+                .is_none_or(|desc_end| desc_end > end)
+            {
+                return Err(malformed());
+            }
+
+            if kind == NT_GNU_BUILD_ID && name_size == 4 && elf.bytes::<4>(name)? == *b"GNU\0" {
+                let mut id = vec![0; desc_size as usize];
+                elf.file.seek(SeekFrom::Start(desc))?;
+                elf.file.read_exact(&mut id)?;
+                return Ok(Some(id.iter().map(|byte| format!("{byte:02x}")).collect()));
+            }
+            note = desc
+                .checked_add(desc_size.next_multiple_of(4))
+                .ok_or_else(malformed)?;
+        }
+    }
+    Ok(None)
+}
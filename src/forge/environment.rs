@@ -0,0 +1,41 @@
+use std::env;
+
+const PREFIX: &str = "${env:";
+
+/// Names of every `${env:VAR}` reference in `contents`
+pub(super) fn references(contents: &str) -> impl Iterator<Item = &str> {
+    contents.match_indices(PREFIX).filter_map(|(i, _)| {
+        let rest = &contents[i + PREFIX.len()..];
+        rest.find('}').map(|end| &rest[..end])
+    })
+}
+
+/// Replaces each `${env:VAR}` in `line` with the value of `VAR` from the process environment. References to unset
+/// variables are left in place and passed to `unset`
+pub(super) fn expand(line: &str, mut unset: impl FnMut(&str)) -> String {
+    let mut out = String::with_capacity(line.len());
+    let mut rest = line;
+
+    while let Some(start) = rest.find(PREFIX) {
+        out.push_str(&rest[..start]);
+        let reference = &rest[start..];
+
+        let Some(end) = reference.find('}') else {
+            rest = reference;
+            break;
+        };
+        let name = &reference[PREFIX.len()..end];
+
+        match env::var(name) {
+            Ok(value) if !name.is_empty() => out.push_str(&value),
+            _ => {
+                unset(name);
+                out.push_str(&reference[..=end]);
+            }
+        }
+        rest = &reference[end + 1..];
+    }
+
+    out.push_str(rest);
+    out
+}
@@ -0,0 +1,137 @@
+use std::{
+    fmt::Write as _,
+    fs, io,
+    path::PathBuf,
+    sync::{Mutex, OnceLock},
+};
+
+use super::{PKG_NAME, color, warnings};
+
+/// How fatal errors are reported, set once from `--error-format`
+static ERROR_FORMAT: OnceLock<ErrorFormat> = OnceLock::new();
+
+#[derive(Clone, Copy, Default)]
+pub(crate) enum ErrorFormat {
+    #[default]
+    Text,
+    /// A single JSON object on stderr, eg. for CI to react to a failure programmatically
+    Json,
+}
+
+impl ErrorFormat {
+    pub(crate) fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "text" => Some(Self::Text),
+            "json" => Some(Self::Json),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            Self::Text => "text",
+            Self::Json => "json",
+        }
+    }
+}
+
+pub(crate) fn set_format(format: ErrorFormat) {
+    let _ = ERROR_FORMAT.set(format);
+}
+
+pub(super) fn format() -> ErrorFormat {
+    ERROR_FORMAT.get().copied().unwrap_or_default()
+}
+
+/// Partially staged tree removed by `exit`, so a failed run never leaves one behind
+static PARTIAL_TREE: Mutex<Option<PathBuf>> = Mutex::new(None);
+
+/// Sets the partially staged tree to remove should the run fail, `None` once it is complete
+pub(super) fn remove_on_exit(tree: Option<PathBuf>) {
+    if let Ok(mut partial) = PARTIAL_TREE.lock() {
+        *partial = tree
+    }
+}
+
+pub(super) fn json_string(value: &str) -> String {
+    let mut out = String::from('"');
+    for char in value.chars() {
+        match char {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            char if char.is_control() => {
+                let _ = write!(out, "\\u{:04x}", char as u32);
+            }
+            char => out.push(char),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// A fatal error a phase returns rather than exiting itself, `main` reports it with `Error::exit`
+#[derive(Debug)]
+pub(crate) struct Error {
+    message: String,
+    /// Classifies the error for `--error-format json`
+    kind: String,
+    /// File type or path the error concerns
+    file: Option<String>,
+}
+
+impl Error {
+    pub(crate) fn new(message: String, kind: &str, file: Option<String>) -> Self {
+        Self {
+            message,
+            kind: kind.to_string(),
+            file,
+        }
+    }
+
+    pub(crate) fn exit(&self) -> ! {
+        exit(&self.message, &self.kind, self.file.as_deref())
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Self {
+        Self::new(err.to_string(), &format!("{:?}", err.kind()), None)
+    }
+}
+
+/// Reports a fatal error and exits. `kind` classifies the error for `--error-format json`, `file` names the file type
+/// or path it concerns
+pub(crate) fn exit(message: &str, kind: &str, file: Option<&str>) -> ! {
+    // Warnings that led up to the error are shown before it
+    warnings::render();
+    match format() {
+        ErrorFormat::Text => {
+            eprintln!(
+                "{PKG_NAME}: {} {message}",
+                color::stderr(color::RED, "Error")
+            )
+        }
+        ErrorFormat::Json => {
+            let mut json = format!(
+                "{{ \"error\": {}, \"kind\": {}",
+                json_string(message),
+                json_string(kind)
+            );
+            if let Some(file) = file {
+                let _ = write!(json, ", \"file\": {}", json_string(file));
+            }
+            eprintln!("{json} }}")
+        }
+    }
+    if let Some(tree) = PARTIAL_TREE
+        .lock()
+        .ok()
+        .and_then(|mut partial| partial.take())
+    {
+        let _ = fs::remove_dir_all(tree);
+    }
+    std::process::exit(1)
+}
@@ -0,0 +1,43 @@
+/// Matches `path` against a glob `pattern` supporting `*`, `**` and `?`. A single `*` never crosses a `/`, `**`
+/// matches across directories. Patterns without a `/` are matched against the file name alone
+pub(crate) fn matches(pattern: &str, path: &str) -> bool {
+    if !pattern.contains('/') {
+        let file_name = path.rsplit('/').next().unwrap_or(path);
+        return matches_from(pattern.as_bytes(), file_name.as_bytes());
+    }
+    let (pattern, path) = (
+        pattern.trim_start_matches('/'),
+        path.trim_start_matches('/'),
+    );
+
+    // `dir/**` also matches `dir` itself so the directory is excluded along with its contents
+    if pattern.strip_suffix("/**") == Some(path) {
+        return true;
+    }
+    matches_from(pattern.as_bytes(), path.as_bytes())
+}
+
+pub(crate) fn matches_any<S: AsRef<str>>(patterns: &[S], path: &str) -> bool {
+    patterns
+        .iter()
+        .any(|pattern| matches(pattern.as_ref(), path))
+}
+
+fn matches_from(pattern: &[u8], text: &[u8]) -> bool {
+    match pattern {
+        [] => text.is_empty(),
+        [b'*', b'*', rest @ ..] => {
+            let rest = rest.strip_prefix(b"/").unwrap_or(rest);
+            (0..=text.len()).any(|i| matches_from(rest, &text[i..]))
+        }
+        [b'*', rest @ ..] => (0..=text.len())
+            .take_while(|&i| i == 0 || text[i - 1] != b'/')
+            .any(|i| matches_from(rest, &text[i..])),
+        [b'?', rest @ ..] => {
+            matches!(text, [first, remaining @ ..] if *first != b'/' && matches_from(rest, remaining))
+        }
+        [expected, rest @ ..] => {
+            matches!(text, [first, remaining @ ..] if first == expected && matches_from(rest, remaining))
+        }
+    }
+}
@@ -0,0 +1,72 @@
+use std::{
+    fmt::Write as _,
+    fs,
+    io::{self, Read},
+    path::Path,
+};
+
+// MD5 is only needed for the `MD5sum` field apt indexes still carry. Not used for anything security relevant
+use md5::Md5;
+// SHA-1 is only needed for the `Checksums-Sha1` field `.changes` files still require
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+
+/// Files are hashed in chunks of this size, memory stays bounded however large a binary or `.deb` is
+const CHUNK_SIZE: usize = 64 * 1024;
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().fold(String::new(), |mut out, byte| {
+        let _ = write!(out, "{byte:02x}");
+        out
+    })
+}
+
+/// Feeds the file at `path` to `update` in fixed size chunks, the file is never read into memory whole
+fn for_each_chunk(path: &Path, mut update: impl FnMut(&[u8])) -> io::Result<()> {
+    let mut file = fs::File::open(path)?;
+    let mut buf = vec![0; CHUNK_SIZE];
+
+    loop {
+        match file.read(&mut buf)? {
+            0 => return Ok(()),
+            read => update(&buf[..read]),
+        }
+    }
+}
+
+/// Hex encoded SHA-256 of the file at `path`
+pub(super) fn sha256_file(path: &Path) -> io::Result<String> {
+    let mut hasher = Sha256::new();
+    for_each_chunk(path, |chunk| hasher.update(chunk))?;
+    Ok(hex(&hasher.finalize()))
+}
+
+/// Hex encoded MD5 and SHA-256 of the file at `path`, computed in a single pass
+pub(super) fn md5_sha256_file(path: &Path) -> io::Result<(String, String)> {
+    let (mut md5, mut sha256) = (Md5::new(), Sha256::new());
+    for_each_chunk(path, |chunk| {
+        md5.update(chunk);
+        sha256.update(chunk)
+    })?;
+    Ok((hex(&md5.finalize()), hex(&sha256.finalize())))
+}
+
+/// Hex encoded MD5, SHA-1 and SHA-256 of the file at `path`, computed in a single pass
+pub(super) fn md5_sha1_sha256_file(path: &Path) -> io::Result<(String, String, String)> {
+    let (mut md5, mut sha1, mut sha256) = (Md5::new(), Sha1::new(), Sha256::new());
+    for_each_chunk(path, |chunk| {
+        md5.update(chunk);
+        sha1.update(chunk);
+        sha256.update(chunk)
+    })?;
+    Ok((
+        hex(&md5.finalize()),
+        hex(&sha1.finalize()),
+        hex(&sha256.finalize()),
+    ))
+}
+
+/// Hex encoded SHA-256 of `bytes`
+pub(super) fn sha256(bytes: &[u8]) -> String {
+    hex(&Sha256::digest(bytes))
+}
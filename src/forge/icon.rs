@@ -0,0 +1,142 @@
+use std::{
+    fs,
+    io::{self, BufReader, Read, Seek, SeekFrom},
+    path::Path,
+};
+
+use super::FileType;
+
+const PNG_SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+const TIFF_WIDTH: u16 = 256;
+const TIFF_HEIGHT: u16 = 257;
+
+/// Width and height of a raster icon read from its header, `None` for formats without fixed dimensions, eg. svg, or
+/// a header that could not be understood
+pub(super) fn dimensions(path: &Path) -> io::Result<Option<(u32, u32)>> {
+    let mut file = BufReader::new(fs::File::open(path)?);
+    let mut magic = [0; 4];
+    if file.read_exact(&mut magic).is_err() {
+        return Ok(None);
+    }
+    file.rewind()?;
+
+    match magic {
+        [0x89, b'P', b'N', b'G'] => png(&mut file),
+        [0xFF, 0xD8, ..] => jpeg(&mut file),
+        [b'I', b'I', 42, 0] | [b'M', b'M', 0, 42] => tiff(&mut file),
+        _ => Ok(None),
+    }
+}
+
+/// The `IHDR` chunk always directly follows the signature
+fn png(file: &mut impl Read) -> io::Result<Option<(u32, u32)>> {
+    let mut header = [0; 24];
+    file.read_exact(&mut header)?;
+    if &header[..8] != PNG_SIGNATURE || &header[12..16] != b"IHDR" {
+        return Ok(None);
+    }
+    let width = u32::from_be_bytes(header[16..20].try_into().expect("4 bytes"));
+    let height = u32::from_be_bytes(header[20..24].try_into().expect("4 bytes"));
+    Ok(Some((width, height)))
+}
+
+/// Walks the segments up to the first start of frame, which records the dimensions
+fn jpeg(file: &mut (impl Read + Seek)) -> io::Result<Option<(u32, u32)>> {
+    let mut byte = [0; 1];
+    file.seek(SeekFrom::Start(2))?;
+    loop {
+        file.read_exact(&mut byte)?;
+        if byte[0] != 0xFF {
+            return Ok(None);
+        }
+        // Any number of 0xFF may pad a marker
+        while byte[0] == 0xFF {
+            file.read_exact(&mut byte)?;
+        }
+        let marker = byte[0];
+        if matches!(marker, 0x01 | 0xD0..=0xD7) {
+            continue;
+        }
+        if marker == 0xD9 {
+            return Ok(None);
+        }
+
+        let mut length = [0; 2];
+        file.read_exact(&mut length)?;
+        let length = u16::from_be_bytes(length);
+        // SOF0 through SOF15, except DHT, JPG and DAC which share the range
+        if matches!(marker, 0xC0..=0xCF) && !matches!(marker, 0xC4 | 0xC8 | 0xCC) {
+            let mut frame = [0; 5];
+            file.read_exact(&mut frame)?;
+            let height = u16::from_be_bytes([frame[1], frame[2]]);
+            let width = u16::from_be_bytes([frame[3], frame[4]]);
+            return Ok(Some((width as u32, height as u32)));
+        }
+        file.seek(SeekFrom::Current(length as i64 - 2))?;
+    }
+}
+
+/// Reads `ImageWidth` and `ImageLength` from the first image file directory
+fn tiff(file: &mut (impl Read + Seek)) -> io::Result<Option<(u32, u32)>> {
+    let mut header = [0; 8];
+    file.read_exact(&mut header)?;
+    let little = header[0] == b'I';
+    let u16_at = |bytes: &[u8]| {
+        let bytes = [bytes[0], bytes[1]];
+        if little {
+            u16::from_le_bytes(bytes)
+        } else {
+            u16::from_be_bytes(bytes)
+        }
+    };
+    let u32_at = |bytes: &[u8]| {
+        let bytes = [bytes[0], bytes[1], bytes[2], bytes[3]];
+        if little {
+            u32::from_le_bytes(bytes)
+        } else {
+            u32::from_be_bytes(bytes)
+        }
+    };
+
+    file.seek(SeekFrom::Start(u32_at(&header[4..]) as u64))?;
+    let mut count = [0; 2];
+    file.read_exact(&mut count)?;
+
+    let (mut width, mut height) = (None, None);
+    for _ in 0..u16_at(&count) {
+        let mut entry = [0; 12];
+        file.read_exact(&mut entry)?;
+        // SHORT or LONG, both stored inline in the value field
+        let value = match u16_at(&entry[2..]) {
+            3 => u16_at(&entry[8..]) as u32,
+            4 => u32_at(&entry[8..]),
+            _ => continue,
+        };
+        match u16_at(&entry) {
+            TIFF_WIDTH => width = Some(value),
+            TIFF_HEIGHT => height = Some(value),
+            _ => (),
+        }
+    }
+    Ok(width.zip(height))
+}
+
+/// Problems with the raster icon at `path` staged as `icon`, whose actual dimensions must match the size it is
+/// staged at
+pub(super) fn validate(icon: FileType, path: &Path) -> Vec<String> {
+    let expected = icon
+        .width()
+        .parse::<u32>()
+        .expect("icon widths are numbers");
+    // A truncated or unusual header is left for the icon theme to reject
+    let Ok(Some((width, height))) = dimensions(path) else {
+        return Vec::new();
+    };
+    if (width, height) == (expected, expected) {
+        return Vec::new();
+    }
+    vec![format!(
+        "icon is {width}x{height} but is staged as {}",
+        icon.resolution()
+    )]
+}
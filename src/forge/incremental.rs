@@ -0,0 +1,126 @@
+use std::{
+    collections::HashMap,
+    fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+use super::{FileType, Variables, control::Control, environment, hash};
+
+/// Where `--incremental` records what the previous run staged, relative to the project directory
+pub(super) const MANIFEST: &str = "build/tmp/.debforge-manifest";
+
+/// Content hashes of each staged file's source, keyed by the staged path
+pub(super) struct Manifest {
+    path: PathBuf,
+    previous: HashMap<PathBuf, (PathBuf, String)>,
+    current: HashMap<PathBuf, (PathBuf, String)>,
+}
+
+impl Manifest {
+    /// Reads the manifest left by the previous run, a missing or unreadable manifest simply restages everything
+    pub(super) fn load(project_dir: &Path) -> Self {
+        let path = project_dir.join(MANIFEST);
+        let previous = fs::read_to_string(&path)
+            .unwrap_or_default()
+            .lines()
+            .filter_map(|line| {
+                let mut fields = line.split('\t');
+                let (hash, source, output) = (fields.next()?, fields.next()?, fields.next()?);
+                Some((
+                    PathBuf::from(output),
+                    (PathBuf::from(source), hash.to_string()),
+                ))
+            })
+            .collect();
+
+        Self {
+            path,
+            previous,
+            current: HashMap::new(),
+        }
+    }
+
+    /// Records the hash of `source` and returns `true` when `output` already holds what staging it would produce
+    pub(super) fn is_fresh(
+        &mut self,
+        vars: &Variables,
+        file_type: FileType,
+        source: &Path,
+        output: &Path,
+    ) -> io::Result<bool> {
+        let hash = if vars.is_rendered(file_type, source) {
+            let contents = fs::read_to_string(source)?;
+            hash::sha256(
+                format!("{contents}\0{}", vars.fingerprint(file_type, &contents)).as_bytes(),
+            )
+        } else {
+            hash::sha256_file(source)?
+        };
+
+        let fresh = output.exists()
+            && self
+                .previous
+                .get(output)
+                .is_some_and(|(previous_source, previous)| {
+                    previous_source == source && *previous == hash
+                });
+
+        self.current
+            .insert(output.to_path_buf(), (source.to_path_buf(), hash));
+        Ok(fresh)
+    }
+
+    /// Removes staged files whose source is no longer staged and writes the manifest for the next run
+    pub(super) fn save(self) -> io::Result<()> {
+        for output in self.previous.keys() {
+            if !self.current.contains_key(output) && output.is_file() {
+                fs::remove_file(output)?;
+            }
+        }
+
+        let mut entries = self.current.iter().collect::<Vec<_>>();
+        entries.sort_by_key(|&(output, _)| output);
+
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut file = io::BufWriter::new(fs::File::create(&self.path)?);
+        for (output, (source, hash)) in entries {
+            writeln!(file, "{hash}\t{}\t{}", source.display(), output.display())?;
+        }
+        file.flush()
+    }
+}
+
+impl Variables {
+    /// Everything other than its source content that shapes a staged text file. Only the replacements `contents`
+    /// actually uses are included, so eg. a new `$Date` does not restage files that never mention it
+    fn fingerprint(&self, file_type: FileType, contents: &str) -> String {
+        let mut input = self
+            .replacements()
+            .into_iter()
+            .filter(|(key, _)| contents.contains(key))
+            .map(|(key, value)| format!("{key}={value}\n"))
+            .collect::<String>();
+        for name in environment::references(contents) {
+            input.push_str(&format!("{name}={:?}\n", std::env::var(name).ok()));
+        }
+
+        match file_type {
+            FileType::Control => {
+                // Every field the config writes, applied to an empty control file so a field added to
+                // `apply_config` is always part of the fingerprint
+                let mut config_fields = Control::parse("");
+                config_fields.apply_config(&self.config);
+                input.push_str(&format!("{:?}{}", self.substvars, config_fields.render()))
+            }
+            FileType::Changelog => input.push_str(&format!("{:?}", self.changelog_entry)),
+            FileType::PostInst if self.primary => {
+                input.push_str(&format!("{:?}", self.postinst_fragment()))
+            }
+            _ => (),
+        }
+        input
+    }
+}
@@ -0,0 +1,44 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use super::{FileType, Variables, control::Control, hash};
+
+/// Writes the stanzas to stdout for `-`, otherwise to the given file
+pub(super) fn write(stanzas: &[String], output: &str) -> io::Result<Option<PathBuf>> {
+    let index = stanzas.join("\n");
+    if output == "-" {
+        print!("{index}");
+        return Ok(None);
+    }
+
+    let path = PathBuf::from(output);
+    if let Some(parent) = path
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+    {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(&path, index)?;
+    Ok(Some(path))
+}
+
+impl Variables {
+    /// `Packages` index stanza of a built `.deb`, the staged control fields followed by its file name, size and hashes
+    pub(super) fn packages_stanza(&self, deb: &Path) -> io::Result<String> {
+        let control = fs::read_to_string(self.get_output_path(FileType::Control))?;
+        let mut stanza = Control::parse(&control);
+
+        let (md5, sha256) = hash::md5_sha256_file(deb)?;
+        let file_name = deb
+            .file_name()
+            .expect("built packages have a file name")
+            .to_string_lossy();
+        stanza.set_if_absent("Filename", &file_name);
+        stanza.set_if_absent("Size", &fs::metadata(deb)?.len().to_string());
+        stanza.set_if_absent("MD5sum", &md5);
+        stanza.set_if_absent("SHA256", &sha256);
+        Ok(stanza.render())
+    }
+}
@@ -0,0 +1,83 @@
+use std::{fs, path::Path};
+
+use super::{PKG_NAME, error::Error};
+use crate::fatal;
+
+const CONTROL: &str = "\
+Package: $LinuxBinaryName
+Version: $Version
+Architecture: $Architecture
+Maintainer: $Maintainer
+Priority: optional
+Description: $BinaryName
+ A longer description of $BinaryName, each line indented by 1 space.
+";
+
+const CHANGELOG: &str = "\
+$LinuxBinaryName ($Version) $Distribution; urgency=medium
+
+  * Initial release.
+
+ -- $Maintainer  $Date
+";
+
+const COPYRIGHT: &str = "\
+Format: https://www.debian.org/doc/packaging-manuals/copyright-format/1.0/
+Upstream-Name: $BinaryName
+
+Files: *
+Copyright: $Maintainer
+License: MIT
+ Replace with the project's license, see https://spdx.org/licenses/
+";
+
+const DESKTOP: &str = "\
+[Desktop Entry]
+Type=Application
+Name=$BinaryName
+Exec=$LinuxBinaryName
+Icon=$LinuxBinaryName
+Terminal=false
+Categories=Utility;
+";
+
+/// Every file `--init` creates, relative to the project directory
+const SKELETON: [(&str, &str); 4] = [
+    ("debian/control", CONTROL),
+    ("debian/changelog", CHANGELOG),
+    ("debian/copyright", COPYRIGHT),
+    ("assets/app.desktop", DESKTOP),
+];
+
+/// Creates skeleton debian files in `project_dir`. Nothing is written if any of them already exists
+pub(crate) fn scaffold(project_dir: &Path) -> Result<(), Error> {
+    let existing = SKELETON
+        .iter()
+        .map(|(path, _)| project_dir.join(path))
+        .filter(|path| path.exists())
+        .collect::<Vec<_>>();
+    if !existing.is_empty() {
+        return Err(fatal!(
+            "--init will not overwrite: {}",
+            existing
+                .iter()
+                .map(|path| format!("'{}'", path.display()))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ));
+    }
+
+    for (path, contents) in SKELETON {
+        let path = project_dir.join(path);
+        fs::create_dir_all(
+            path.parent()
+                .expect("skeleton files are within a directory"),
+        )?;
+        fs::write(&path, contents)?;
+        println!("Created: '{}'", path.display())
+    }
+    println!(
+        "{PKG_NAME}: Initialized project, icons named with their size, eg. `icon-256x256.png`, belong in assets"
+    );
+    Ok(())
+}
@@ -0,0 +1,105 @@
+use std::{
+    fs,
+    path::{Component, Path, PathBuf},
+};
+
+use super::{FileType, Variables, error::Error, remove_link, symlink};
+use crate::fatal;
+
+/// Parses a dh_link style `debian/links` file, each line holding a link target followed by the link to create, both
+/// absolute install paths. Leading `/` are optional
+fn parse(contents: &str, input: &Path) -> Result<Vec<(PathBuf, PathBuf)>, Error> {
+    contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(
+            |line| match line.split_whitespace().collect::<Vec<_>>()[..] {
+                [target, link] => Ok((
+                    PathBuf::from(target.trim_start_matches('/')),
+                    PathBuf::from(link.trim_start_matches('/')),
+                )),
+                _ => Err(fatal!(
+                    "links line: '{line}' must be a target and a link path in: '{}'",
+                    input.display()
+                )),
+            },
+        )
+        .collect()
+}
+
+/// Resolves `.` and `..` lexically, `None` if the path climbs above the root
+fn normalize(path: &Path) -> Option<PathBuf> {
+    let mut out = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::Normal(name) => out.push(name),
+            Component::ParentDir if !out.pop() => return None,
+            _ => (),
+        }
+    }
+    Some(out)
+}
+
+/// Target of `link` relative to its directory when both share a top-level directory, eg. `usr/lib/libx.so.1` from
+/// `usr/lib/libx.so` becomes `libx.so.1`. Policy keeps links between top-level directories absolute
+fn relative_target(target: &Path, link: &Path) -> Result<Option<PathBuf>, Error> {
+    let (Some(target), Some(link)) = (normalize(target), normalize(link)) else {
+        return Ok(None);
+    };
+    if target.components().next() != link.components().next() {
+        return Ok(None);
+    }
+
+    let Some(link_dir) = link.parent() else {
+        return Ok(None);
+    };
+    let common = target
+        .components()
+        .zip(link_dir.components())
+        .take_while(|(a, b)| a == b)
+        .count();
+
+    let mut relative = PathBuf::new();
+    for _ in link_dir.components().skip(common) {
+        relative.push("..");
+    }
+    relative.extend(target.components().skip(common));
+
+    // The link must still resolve to the same place it would have with an absolute target
+    if normalize(&link_dir.join(&relative)).as_ref() != Some(&target) {
+        return Err(fatal!(
+            "computed relative link: '{}' for: '/{}' does not resolve to: '/{}'",
+            relative.display(),
+            link.display(),
+            target.display()
+        ));
+    }
+    Ok(Some(relative))
+}
+
+impl Variables {
+    /// Creates the symlinks listed in a links file within the stage root, returning the created links
+    pub(super) fn write_links(&self, input: &Path, relative: bool) -> Result<Vec<PathBuf>, Error> {
+        let contents = self.render_text(FileType::Links, input)?;
+        let stage_root = self.get_stage_root();
+        let mut created = Vec::new();
+
+        for (target, link) in parse(&contents, input)? {
+            let relative_target = match relative {
+                true => relative_target(&target, &link)?,
+                false => None,
+            };
+            let link_target = relative_target.unwrap_or_else(|| Path::new("/").join(&target));
+
+            let output = stage_root.join(&link);
+            fs::create_dir_all(output.parent().expect("links are within the stage root"))?;
+            remove_link(&output)?;
+
+            symlink(&link_target, &output)?;
+
+            created.push(output);
+        }
+        Ok(created)
+    }
+}
@@ -1,46 +1,276 @@
+mod changelog;
+mod changes;
+pub(crate) mod color;
+mod conditional;
+pub(crate) mod config;
+mod control;
+mod copyright;
+mod deb;
 mod deb_files;
+mod desktop;
+mod discover;
+mod dump;
+mod elf;
+mod environment;
+pub(crate) mod error;
+pub(crate) mod glob;
+mod hash;
+mod icon;
+mod incremental;
+mod index;
+mod init;
+mod links;
+mod ownership;
+mod prepare;
+mod relocate;
+mod report;
+mod scripts;
+mod shellcheck;
+mod shlibs;
+mod source;
+mod substvars;
+mod summary;
+mod tool;
+mod triggers;
+mod upx;
+mod user_install;
+mod validate;
+mod verify;
+pub(crate) mod warnings;
+mod watch;
+mod workspace;
 
 use std::{
     collections::HashMap,
     env,
     ffi::OsString,
     fs::{self, DirEntry},
-    io::{self, BufRead, BufReader, BufWriter, Write},
+    io::{self, BufReader, BufWriter, Read, Write},
     path::{Path, PathBuf},
+    sync::atomic::{AtomicUsize, Ordering},
 };
 
 use crate::args::*;
+use conditional::Conditions;
+use config::{Config, PROJECT_CONFIG};
+use control::Control;
+pub(crate) use deb::{Compression, DebBuilder};
 use deb_files::*;
+pub(crate) use deb_files::{
+    DEFAULT_STAGE_TEMPLATE, FileType, PackageType, STAGE_TEMPLATE_PLACEHOLDERS,
+};
+use discover::{Discovered, Discovery};
+use error::Error;
+use flate2::{GzBuilder, bufread::GzDecoder};
+use incremental::Manifest;
+use report::PackageReport;
+pub(crate) use scripts::Owner;
+use summary::Summary;
+use toml::{Table, Value};
+use validate::Requirements;
 
 pub(crate) const PKG_NAME: &str = env!("CARGO_PKG_NAME");
 pub(crate) const TOML: &str = "Cargo.toml";
 
 const TEMP_DIR: &str = "tmp";
+const UTF8_BOM: &[u8] = b"\xEF\xBB\xBF";
+const GZIP_MAGIC: &[u8] = b"\x1F\x8B";
+/// Prefix of the placeholders that expose the fields of a package's control file
+const CONTROL_PLACEHOLDER: &str = "$Control";
+/// Generous for any project, yet stops a scan of the wrong directory, eg. `$HOME`
+pub(crate) const DEFAULT_MAX_FILES: usize = 100_000;
+/// Docs over 4 KiB are compressed, as `dh_compress` does
+pub(crate) const DEFAULT_COMPRESS_THRESHOLD: u64 = 4096;
 const SEARCH_DIRS: [SearchDir; 3] = [SearchDir::Assets, SearchDir::Build, SearchDir::Debian];
-const REQUIRED_DEB_FILES: [FileType; 3] =
+pub(crate) const REQUIRED_DEB_FILES: [FileType; 3] =
     [FileType::Control, FileType::Changelog, FileType::Copyright];
 
 #[macro_export]
 macro_rules! exit_err {
+    (kind: $kind:literal, file: $file:expr; $($arg:tt)*) => {{
+        $crate::forge::error::exit(&format!($($arg)*), $kind, Some(&$file))
+    }};
+    (kind: $kind:literal; $($arg:tt)*) => {{
+        $crate::forge::error::exit(&format!($($arg)*), $kind, None)
+    }};
     ($($arg:tt)*) => {{
-        eprintln!("{}: Error {}", $crate::forge::PKG_NAME, format_args!($($arg)*));
-        std::process::exit(1);
+        $crate::forge::error::exit(&format!($($arg)*), "Error", None)
     }};
 }
 
-type DebFiles = HashMap<FileType, PathBuf>;
+/// Builds the `error::Error` a phase returns for `main` to report, with the same `kind` and `file` as `exit_err!`
+#[macro_export]
+macro_rules! fatal {
+    (kind: $kind:literal, file: $file:expr; $($arg:tt)*) => {{
+        $crate::forge::error::Error::new(format!($($arg)*), $kind, Some($file.to_string()))
+    }};
+    (kind: $kind:literal; $($arg:tt)*) => {{
+        $crate::forge::error::Error::new(format!($($arg)*), $kind, None)
+    }};
+    ($($arg:tt)*) => {{
+        $crate::forge::error::Error::new(format!($($arg)*), "Error", None)
+    }};
+}
+
+/// Entries visited by every scan so far, limited by `--max-files`
+static SCANNED: AtomicUsize = AtomicUsize::new(0);
+static MAX_FILES: AtomicUsize = AtomicUsize::new(DEFAULT_MAX_FILES);
+
+/// Collects a warning, rendered with every other warning once the run ends. `kind` classifies the warning for
+/// `--error-format json`, `file` names the path it concerns
+#[macro_export]
+macro_rules! warn {
+    (kind: $kind:literal, file: $file:expr; $($arg:tt)*) => {{
+        $crate::forge::warnings::push($crate::forge::warnings::Warning {
+            kind: $kind,
+            message: format!($($arg)*),
+            file: Some($file.to_string()),
+        })
+    }};
+    (kind: $kind:literal; $($arg:tt)*) => {{
+        $crate::forge::warnings::push($crate::forge::warnings::Warning {
+            kind: $kind,
+            message: format!($($arg)*),
+            file: None,
+        })
+    }};
+    ($($arg:tt)*) => {{
+        $crate::forge::warnings::push($crate::forge::warnings::Warning {
+            kind: "Warning",
+            message: format!($($arg)*),
+            file: None,
+        })
+    }};
+}
+
+/// Reports a validation problem as a warning, or returns it as an error under `--strict`
+#[macro_export]
+macro_rules! strict_warn {
+    ($strict:expr, $($arg:tt)*) => {{
+        if $strict {
+            Err($crate::fatal!($($arg)*))
+        } else {
+            $crate::warn!($($arg)*);
+            Ok::<(), $crate::forge::error::Error>(())
+        }
+    }};
+}
+
+/// Files found while scanning. Architecture suffixed files, eg. `postinst.arm64`, are kept apart so they only replace
+/// their unsuffixed counterpart when staging for the matching `Architecture`
+#[derive(Default)]
+struct DebFiles {
+    files: HashMap<FileType, PathBuf>,
+    arch_files: HashMap<(FileType, Architecture), PathBuf>,
+    /// Search directory each file was found in, `None` for files in the project directory itself
+    origins: HashMap<(FileType, Option<Architecture>), Option<SearchDir>>,
+    /// Search directory whose file wins when more than 1 file of the same type is found
+    prefer: Option<SearchDir>,
+    /// Explicit icon sizes by file name from `Config::icons`
+    icons: HashMap<String, FileType>,
+    /// Files found in a `copyright.d` directory, in file name order
+    copyright_fragments: Vec<PathBuf>,
+    /// `debian/user` directory of templates for `--user-install`
+    user_templates: Option<PathBuf>,
+    /// Package name a debhelper style file name may start with, eg. `my-app.postinst`, from `--debian-dir`
+    package_prefix: Option<String>,
+    /// Whether `<name>.<locale>.desktop` files are translations merged into the desktop entry, from `--locale-desktop`
+    locale_desktop: bool,
+    /// Translated desktop entry fragments, in file name order
+    desktop_locales: Vec<PathBuf>,
+}
+
+/// A binary package staged into its own tree. The first package is the primary package, it receives the project
+/// binary along with any files found outside of the other packages' directories
+struct Package {
+    name: String,
+    files: DebFiles,
+    /// Source package whose doc directory this package's doc directory links to under `--doc-link`
+    doc_link: Option<String>,
+}
 
 pub struct Forge {
     vars: Variables,
-    files: DebFiles,
+    packages: Vec<Package>,
+    binaries: Vec<(Architecture, PathBuf)>,
+    /// `Some(excludes)` when an upstream source tarball should be written
+    orig_tarball: Option<Vec<String>>,
+    quiet: bool,
+    check_ownership: bool,
+    shellcheck: bool,
+    /// Whether a stale `Installed-Size` in a staged control file is corrected instead of reported
+    fix_installed_size: bool,
+    /// Hashes of what the previous run staged under `--incremental`
+    manifest: Option<Manifest>,
+    relative_symlinks: bool,
+    build: Option<DebBuilder>,
+    /// Where the `Packages` index of the built packages is written, `-` for stdout
+    packages_index: Option<String>,
+    changes: bool,
+    /// Inspects each built package with dpkg-deb, from `--verify-deb`
+    verify_deb: bool,
+    keep_staging: bool,
+    compress_binary: bool,
+    max_size: Option<u64>,
+    /// File types restaged under `--only`, empty stages everything
+    only: Vec<FileType>,
+    /// File types left out under `--skip`
+    skip: Vec<FileType>,
+    /// Where `--summary-json` writes the report of the run, `-` for stdout
+    summary_json: Option<String>,
 }
 
 struct Variables {
     project_dir: PathBuf,
     binary_name: String,
     linux_binary_name: String,
+    /// Name of the package currently being staged, used for its staging and doc directories
+    package_name: String,
+    /// Whether the package currently being staged is the primary package
+    primary: bool,
     version: String,
     architecture: Architecture,
+    config: Config,
+    date: String,
+    changelog_entry: Option<String>,
+    /// DEP-5 copyright generated under `--generate-copyright` when the primary package does not provide one
+    generated_copyright: Option<String>,
+    opt_vendor: Option<String>,
+    /// Installs the binary to `usr/sbin`, from `--sbin`
+    sbin: bool,
+    /// Absolute directory the binary is installed to, exposed as `$BinDir`
+    bin_dir: String,
+    /// Installs the primary package's `wrapper.sh` as the command and the binary beside it, from `--wrapper`
+    wrapper: bool,
+    /// Absolute path of the binary itself, exposed as `$BinaryPath`. Differs from `$BinDir` under `--wrapper`
+    binary_path: String,
+    /// Absolute path of the installed command, exposed as `$ExecPath`. The wrapper under `--wrapper`
+    exec_path: String,
+    /// Whether this run stages the command at `exec_path`, a `$ExecPath` reference otherwise warns
+    exec_staged: bool,
+    stage_template: Option<String>,
+    /// Plain install tree that replaces the stage root under `--dest-root`, without any `DEBIAN` files
+    dest_root: Option<PathBuf>,
+    /// Whether the package is being staged into a hidden sibling of its stage root, renamed into place once complete
+    partial: bool,
+    /// Globs of provided files that are copied as is even when their file type is text
+    binary_copy: Vec<String>,
+    /// Docs other than changelogs larger than this many bytes are staged gzip compressed, from `--compress-threshold`
+    compress_threshold: u64,
+    /// Whether the package currently being staged provides an upstream changelog, the Debian changelog is then staged
+    /// as `changelog.Debian`
+    split_changelog: bool,
+    /// `${name}` values from the `substvars` file of the package currently being staged
+    substvars: Vec<(String, String)>,
+    /// `$Control<Field>` placeholders parsed from the control file of the package currently being staged
+    control_fields: Vec<(String, String)>,
+    strict: bool,
+    reproducible: bool,
+    /// Ownership the primary package's postinst applies, from `--owner`
+    owners: Vec<Owner>,
+    /// Whether the primary package stages `debian/user` templates copied into the installing user's home, from
+    /// `--user-install`
+    user_install: bool,
 }
 
 impl Args {
@@ -49,144 +279,995 @@ impl Args {
         self.binary_name.is_some() && self.version.is_some()
     }
 
-    fn conditionally_parse_toml(&mut self) -> io::Result<()> {
-        if self.has_toml_fields() {
-            return Ok(());
-        }
-
-        fn try_parse_field(line: &str, field: &'static str) -> Option<String> {
-            line.strip_prefix(field)
-                .map(|rest| rest.trim_matches([' ', '\'', '\"', '=']).to_string())
-        }
-
-        let toml = fs::File::open(self.project_dir.join(TOML)).inspect_err(|err| {
-            if let io::ErrorKind::NotFound = err.kind() {
+    /// Fills in the binary name and version if they were not supplied and returns the settings declared in the
+    /// `[package.metadata.debforge]` table along with the defaults inferred from `[package]`
+    fn parse_toml(&mut self) -> io::Result<(Config, Config)> {
+        let contents = match fs::read_to_string(self.project_dir.join(TOML)) {
+            Ok(contents) => contents,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {
+                if self.has_toml_fields() {
+                    return Ok(Default::default());
+                }
                 exit_err!(
                     "failed to find '{TOML}' at: '{}'",
                     self.project_dir.display()
                 )
             }
-        })?;
-        let reader = BufReader::new(toml);
-
-        for line in reader.lines() {
-            let line = line?;
-            let line = line.trim_start();
+            Err(err) => return Err(err),
+        };
 
-            if let Some(Some(name)) = self
-                .binary_name
-                .is_none()
-                .then(|| try_parse_field(line, "name"))
-            {
-                self.binary_name = Some(name)
-            } else if let Some(Some(version_str)) = self
-                .version
-                .is_none()
-                .then(|| try_parse_field(line, "version"))
-            {
-                self.version = Some(version_str)
-            }
+        let manifest = contents
+            .parse::<Table>()
+            .unwrap_or_else(|err| exit_err!("failed to parse {TOML}: {err}"));
 
+        let Some(mut package) = manifest.get("package").and_then(Value::as_table).cloned() else {
             if self.has_toml_fields() {
-                break;
+                return Ok(Default::default());
             }
+            exit_err!("failed to parse {TOML}")
+        };
+        workspace::inherit(&self.project_dir, &manifest, &mut package)?;
+
+        if self.binary_name.is_none() {
+            self.binary_name = config::select_binary(&manifest, &self.features)
+                .or_else(|| config::string(&package, "name", TOML));
+        }
+        if self.version.is_none() {
+            self.version = config::string(&package, "version", TOML);
         }
 
         if !self.has_toml_fields() {
             exit_err!("failed to parse {TOML}")
         }
 
+        let metadata = package
+            .get("metadata")
+            .and_then(Value::as_table)
+            .and_then(|metadata| metadata.get(PKG_NAME))
+            .and_then(Value::as_table)
+            .map(|metadata| Config::from_metadata(metadata, TOML))
+            .unwrap_or_default();
+
         if self.dry_run {
             println!("Parsed {TOML}")
         }
 
-        Ok(())
+        Ok((metadata, Config::from_package(&package)))
+    }
+
+    /// Every source of settings from highest to lowest precedence, named for `--dump-config`
+    fn config_layers(&mut self) -> io::Result<Vec<(&'static str, Config)>> {
+        let (metadata, package) = self.parse_toml()?;
+        let command_line = std::mem::take(&mut self.config);
+        // An explicit `--target` wins, the environment is not consulted at all
+        let host_targets = match command_line.targets.is_empty() {
+            true => Architecture::from_host_env().into_iter().collect(),
+            false => Vec::new(),
+        };
+        Ok(vec![
+            ("command line", command_line),
+            (
+                "DEB_HOST_ARCH",
+                Config {
+                    targets: host_targets,
+                    ..Default::default()
+                },
+            ),
+            (
+                PROJECT_CONFIG,
+                Config::from_project_file(&self.project_dir)?,
+            ),
+            ("Cargo.toml metadata", metadata),
+            ("Cargo.toml package", package),
+        ])
     }
 }
 
 impl Variables {
     fn from(mut args: Args) -> io::Result<Self> {
-        args.conditionally_parse_toml()?;
+        let layers = args.config_layers()?;
+        Ok(Self::from_layers(args, &layers))
+    }
+
+    fn from_layers(args: Args, layers: &[(&'static str, Config)]) -> Self {
+        let mut config = layers.iter().fold(Config::default(), |config, (_, layer)| {
+            config.or(layer.clone())
+        });
+        if config.targets.is_empty() {
+            // Nothing requested a target, so the default must not be one the project rejects
+            let default = config.supported_architectures.first().copied();
+            config.targets.push(default.unwrap_or_default());
+        }
+        config.check_supported_targets();
 
         let binary_name = args
             .binary_name
-            .expect("`conditionally_parse_toml` will return early before this is `None`");
-        Ok(Self {
+            .expect("`parse_toml` will exit before this is `None`");
+        let mut version = args
+            .version
+            .expect("`parse_toml` will exit before this is `None`");
+        if let Some(suffix) = args.version_suffix.as_deref() {
+            version.push_str(suffix);
+            if let Some(problem) = validate::version(&version) {
+                exit_err!("--version-suffix: '{suffix}' does not form a valid version, {problem}")
+            }
+        }
+        let mut vars = Self {
             project_dir: args.project_dir,
             linux_binary_name: binary_name.replace('_', "-"),
+            package_name: args
+                .package_name
+                .unwrap_or_else(|| binary_name.replace('_', "-")),
+            primary: true,
             binary_name,
-            version: args
-                .version
-                .expect("`conditionally_parse_toml` will return early before this is `None`"),
-            architecture: args.architecture,
-        })
+            version,
+            architecture: config.targets[0],
+            config,
+            date: changelog::rfc2822(changelog::build_timestamp()),
+            changelog_entry: None,
+            generated_copyright: None,
+            opt_vendor: args.opt_vendor,
+            sbin: args.sbin,
+            bin_dir: String::new(),
+            wrapper: args.wrapper,
+            binary_path: String::new(),
+            exec_path: String::new(),
+            exec_staged: true,
+            stage_template: args.stage_template,
+            dest_root: args.dest_root,
+            partial: false,
+            control_fields: Vec::new(),
+            substvars: Vec::new(),
+            split_changelog: false,
+            binary_copy: args.binary_copy,
+            compress_threshold: args.compress_threshold,
+            strict: args.strict,
+            reproducible: args.reproducible,
+            owners: args.owners,
+            user_install: args.user_install,
+        };
+        vars.bin_dir = match vars.get_opt_dir() {
+            Some(opt_dir) => format!("/{opt_dir}/bin"),
+            None => format!("/{}", vars.default_bin_dir()),
+        };
+        vars.exec_path = format!("{}/{}", vars.bin_dir, vars.linux_binary_name);
+        let binary_path = vars.get_output_path(FileType::Binary);
+        vars.binary_path = format!(
+            "/{}",
+            binary_path
+                .strip_prefix(vars.get_stage_root())
+                .expect("staged files are within the stage root")
+                .to_string_lossy()
+                .replace('\\', "/")
+        );
+        vars
     }
 
-    fn replacements(&self) -> [(&'static str, &str); 5] {
-        [
-            ("$BinaryName", &self.binary_name),
+    fn replacements(&self) -> Vec<(&str, &str)> {
+        let mut replacements = vec![
+            ("$BinaryName", self.binary_name.as_str()),
             ("$LinuxBinaryName", &self.linux_binary_name),
+            ("$BinDir", &self.bin_dir),
+            ("$BinaryPath", &self.binary_path),
+            ("$ExecPath", &self.exec_path),
+            // Before `$Version`, which would otherwise replace the start of it
+            ("$VersionMajor", self.version_part(0)),
+            ("$VersionMinor", self.version_part(1)),
+            ("$VersionPatch", self.version_part(2)),
             ("$Version", &self.version),
             ("$Target", self.architecture.target()),
             ("$Architecture", self.architecture.short()),
-        ]
-    }
+            ("$Date", &self.date),
+            ("$Distribution", self.config.distribution()),
+        ];
 
-    fn write_file(&self, file_type: FileType, input: &Path) -> io::Result<()> {
-        let mut output_dir = self.get_file_type_path(file_type);
-        fs::create_dir_all(&output_dir)?;
-        output_dir.push(
-            file_type
-                .output_file_name(&self.linux_binary_name)
-                .as_path(),
+        if let Some(maintainer) = self.config.maintainer.as_deref() {
+            replacements.push(("$Maintainer", maintainer));
+        }
+        if let Some(section) = self.config.section.as_deref() {
+            replacements.push(("$Section", section));
+        }
+        if let Some(homepage) = self.config.homepage.as_deref() {
+            replacements.push(("$Homepage", homepage));
+        }
+        replacements.extend(
+            self.control_fields
+                .iter()
+                .map(|(key, value)| (key.as_str(), value.as_str())),
         );
 
+        replacements
+    }
+
+    fn version_part(&self, index: usize) -> &str {
+        version_part(&self.version, index)
+    }
+
+    /// Makes `package` the package currently being staged
+    fn select_package(&mut self, package: &Package, primary: bool) {
+        self.package_name.clone_from(&package.name);
+        self.primary = primary;
+        self.split_changelog = package.files.contains(FileType::UpstreamChangelog);
+    }
+
+    /// Exposes the fields of a package's control file as `$Control<Field>` placeholders, eg. `Multi-Arch` becomes
+    /// `$ControlMultiArch`. The control file is read with only the other placeholders so it can still use `$Version`,
+    /// a `$Control` placeholder within the control file itself is left unresolved
+    fn load_control_fields(&mut self, files: &DebFiles) -> Result<(), Error> {
+        self.control_fields.clear();
+        if !files.files.contains_key(&FileType::Control) {
+            return Ok(());
+        }
+
+        let (_, control) = self.read_control(files)?;
+        self.control_fields = control
+            .fields()
+            .iter()
+            .map(|(field, value)| {
+                let name = field.replace('-', "");
+                (format!("{CONTROL_PLACEHOLDER}{name}"), value.clone())
+            })
+            .collect();
+        Ok(())
+    }
+
+    /// Warns about any `$Placeholder` left in an already substituted `line`. Only CamelCase names are considered so
+    /// shell variables like `$HOME` or `$1` in maintainer scripts are not reported
+    fn check_placeholders(&self, line: &str, input: &Path) {
+        for name in placeholder_names(line) {
+            warn!(
+                kind: "UnresolvedPlaceholder", file: input.display();
+                "unresolved placeholder: '${name}' in: '{}'",
+                input.display()
+            )
+        }
+    }
+
+    /// Whether `input` is staged as text with its placeholders substituted. Files matching a `--binary-copy` glob,
+    /// relative to the project directory, are copied byte for byte instead
+    fn is_rendered(&self, file_type: FileType, input: &Path) -> bool {
         if !file_type.is_text() {
+            return false;
+        }
+        let relative = input.strip_prefix(&self.project_dir).unwrap_or(input);
+        !glob::matches_any(
+            &self.binary_copy,
+            &relative.to_string_lossy().replace('\\', "/"),
+        )
+    }
+
+    fn write_file(&self, file_type: FileType, input: &Path) -> Result<(), Error> {
+        fs::create_dir_all(self.get_file_type_path(file_type))?;
+        let output_dir = self.get_output_path(file_type);
+
+        if file_type == FileType::Binary
+            && let Some(extension) = input
+                .extension()
+                .and_then(|extension| extension.to_str())
+                .filter(|extension| BINARY_COMPRESSIONS.contains(extension))
+        {
+            return decompress_binary(input, extension, &output_dir);
+        }
+
+        if file_type.is_compressible() {
+            let contents = match self.is_rendered(file_type, input) {
+                true => self.render_text(file_type, input)?.into_bytes(),
+                false => fs::read(input)?,
+            };
+            return Ok(self.write_doc(file_type, &contents)?);
+        }
+
+        if !self.is_rendered(file_type, input) {
+            if file_type.is_icon() {
+                for problem in icon::validate(file_type, input) {
+                    strict_warn!(self.strict, "{problem} in: '{}'", input.display())?
+                }
+            }
             fs::copy(input, output_dir)?;
             return Ok(());
         }
 
-        let input = fs::File::open(input)?;
-        let input = BufReader::new(input);
+        write_staged(&output_dir, self.render_text(file_type, input)?.as_bytes())?;
 
-        let output = fs::File::create(&output_dir)?;
-        let mut output = BufWriter::new(output);
+        #[cfg(unix)]
+        if file_type == FileType::Wrapper {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(output_dir, fs::Permissions::from_mode(0o755))?;
+        }
+        Ok(())
+    }
+
+    /// Substitutes placeholders and applies any file type specific processing and validation to a text file
+    fn render_text(&self, file_type: FileType, input: &Path) -> Result<String, Error> {
+        let text = read_text(input)?;
+        if text.contains("\r\n") {
+            warn!(
+                kind: "LineEndings", file: input.display();
+                "CRLF line endings were converted to LF in: '{}'",
+                input.display()
+            )
+        }
 
-        let replacements = self.replacements();
+        if !self.exec_staged && text.contains("$ExecPath") {
+            strict_warn!(
+                self.strict,
+                "$ExecPath: '{}' in: '{}' is not staged by this run",
+                self.exec_path,
+                input.display()
+            )?
+        }
+
+        let mut replacements = self.replacements();
+        if file_type == FileType::Control {
+            replacements.retain(|(key, _)| !key.starts_with(CONTROL_PLACEHOLDER));
+        }
+        let mut contents = String::new();
+        let mut conditions = (file_type == FileType::Control).then(|| Conditions::new(self, input));
+        let mut substituted = false;
+        // Collected rather than reported from within the expansion, under `--strict` the first is returned
+        let (mut undefined, mut unset) = (Vec::new(), Vec::new());
 
-        for line in input.lines() {
-            let mut line = line?;
-            for (key, value) in replacements {
+        for line in text.lines() {
+            if let Some(conditions) = conditions.as_mut()
+                && !conditions.keep(line)
+            {
+                continue;
+            }
+            let mut line = line.to_string();
+            for &(key, value) in replacements.iter() {
                 line = line.replace(key, value);
             }
+            if file_type == FileType::Control && line.contains("${") {
+                substituted = true;
+                line = substvars::expand(&line, &self.substvars, |name| {
+                    undefined.push(name.to_string())
+                });
+            }
+            line = environment::expand(&line, |name| unset.push(name.to_string()));
+            self.check_placeholders(&line, input);
 
-            line.push('\n');
-            output.write_all(line.as_bytes())?;
+            contents.push_str(&line);
+            contents.push('\n');
         }
+        if let Some(Err(err)) = conditions.map(Conditions::finish) {
+            return Err(fatal!("{err}"));
+        }
+        for name in undefined {
+            strict_warn!(
+                self.strict,
+                "substitution variable: '${{{name}}}' in: '{}' is not defined by a substvars file",
+                input.display()
+            )?
+        }
+        for name in unset {
+            strict_warn!(
+                self.strict,
+                "environment variable: '{name}' is not set in: '{}'",
+                input.display()
+            )?
+        }
+
+        if file_type == FileType::Control {
+            let mut control = self.parse_control(&contents, input)?;
+            if substituted {
+                substvars::tidy(&mut control);
+            }
+            control.apply_config(&self.config);
+            contents = control.render();
+        }
+
+        if file_type == FileType::Changelog
+            && let Some(entry) = self.changelog_entry.as_deref()
+        {
+            if changelog::top_version(&contents) == Some(self.version.as_str()) {
+                warn!(
+                    "changelog already has an entry for version: {}, skipped generating one from git",
+                    self.version
+                )
+            } else {
+                contents.insert_str(0, &format!("{entry}\n"));
+            }
+        }
+
+        if file_type == FileType::PostInst
+            && self.primary
+            && let Some(fragment) = self.postinst_fragment()
+        {
+            contents = scripts::insert_fragment(&contents, &fragment);
+        }
+
+        let problems = match file_type {
+            FileType::Watch => watch::validate(&contents),
+            FileType::Triggers => triggers::validate(&contents),
+            _ => Vec::new(),
+        };
+        for problem in problems {
+            strict_warn!(self.strict, "{problem} in: '{}'", input.display())?
+        }
+
+        Ok(contents)
+    }
+
+    /// Links the default binary location to the binary installed under `/opt` so it is still found on `PATH`
+    fn write_opt_symlink(&self, opt_dir: &str) -> Result<PathBuf, Error> {
+        let mut link = self.get_stage_root();
+        link.push(self.default_bin_dir());
+        fs::create_dir_all(&link)?;
+        link.push(&self.linux_binary_name);
+        remove_link(&link)?;
+
+        let target = format!("/{opt_dir}/bin/{}", self.linux_binary_name);
+
+        symlink(Path::new(&target), &link)?;
+        Ok(link)
+    }
+
+    /// Writes `file_type` of `package`, as it would be staged for the first target, to stdout for `--emit`
+    fn emit(&mut self, file_type: FileType, package: &Package) -> Result<(), Error> {
+        self.select_package(package, true);
+        self.load_control_fields(&package.files)?;
+        let files = package.files.for_architecture(self.architecture);
+        self.load_substvars(files.get(&FileType::Substvars).copied())?;
+
+        let Some(&input) = files.get(&file_type) else {
+            let generated = match file_type {
+                FileType::Changelog => self.changelog_entry.clone(),
+                FileType::Copyright => self.generated_copyright.clone(),
+                FileType::PostInst => self
+                    .postinst_fragment()
+                    .map(|fragment| scripts::generated_postinst(&fragment)),
+                _ => None,
+            };
+            let Some(generated) = generated else {
+                return Err(fatal!(
+                    "--emit found no {file_type:?} file for package: '{}'",
+                    package.name
+                ));
+            };
+            print!("{generated}");
+            return Ok(());
+        };
+        let contents = match self.is_rendered(file_type, input) {
+            true => self.render_text(file_type, input)?.into_bytes(),
+            false => fs::read(input)?,
+        };
+        Ok(io::stdout().write_all(&contents)?)
+    }
+
+    /// Stages a compressible doc, gzip compressed when it is larger than `--compress-threshold`. Already gzipped
+    /// contents, eg. from `--binary-copy`, are staged as is under the `.gz` name. A copy a previous run staged under
+    /// the other name is removed
+    fn write_doc(&self, file_type: FileType, contents: &[u8]) -> io::Result<()> {
+        let plain = self.get_uncompressed_output_path(file_type);
+        let compressed = deb_files::gz_path(&plain);
+        let gzipped = contents.starts_with(GZIP_MAGIC);
+        let (output, stale) = if gzipped
+            || file_type.is_always_compressed()
+            || contents.len() as u64 > self.compress_threshold
+        {
+            (compressed, plain)
+        } else {
+            (plain, compressed)
+        };
+        if stale.exists() {
+            fs::remove_file(&stale)?
+        }
+        if gzipped {
+            return fs::write(&output, contents);
+        }
+        write_staged(&output, contents)
+    }
+
+    /// Writes the generated changelog entry as the entire changelog when the project does not provide one
+    fn write_generated_changelog(&self, entry: &str) -> io::Result<()> {
+        fs::create_dir_all(self.get_file_type_path(FileType::Changelog))?;
+        self.write_doc(FileType::Changelog, entry.as_bytes())
+    }
+
+    /// Writes the generated copyright file when the project does not provide one
+    fn write_generated_copyright(&self, copyright: &str) -> io::Result<()> {
+        fs::create_dir_all(self.get_file_type_path(FileType::Copyright))?;
+        fs::write(self.get_output_path(FileType::Copyright), copyright)
+    }
+
+    /// Removes all of `build/tmp` for `--clean`
+    fn clean_temp_dir(&self) -> io::Result<()> {
+        let temp_dir = self.project_dir.join("build").join(TEMP_DIR);
+        if temp_dir.is_dir() {
+            fs::remove_dir_all(&temp_dir)?;
+            println!("Removed: '{}'", temp_dir.display())
+        }
+        Ok(())
+    }
+
+    /// Errors before anything is written when the stage root of any target and package could not be written to, so a
+    /// read-only mount is reported up front rather than leaving a half staged tree behind
+    fn check_writable(
+        &mut self,
+        packages: &[Package],
+        architectures: &[Architecture],
+    ) -> Result<(), Error> {
+        let mut checked = Vec::new();
+        for &architecture in architectures {
+            self.architecture = architecture;
+            for (i, package) in packages.iter().enumerate() {
+                self.select_package(package, i == 0);
+                let stage_root = self.get_stage_root();
+                // The stage root and its parents are created while staging, their nearest existing ancestor is probed
+                let Some(existing) = stage_root.ancestors().find(|dir| dir.is_dir()) else {
+                    continue;
+                };
+                if checked.iter().any(|dir| dir == existing) {
+                    continue;
+                }
+                if let Err(err) = probe_writable(existing) {
+                    return Err(fatal!(
+                        "can not stage to: '{}', '{}' is not writable: {err}",
+                        stage_root.display(),
+                        existing.display()
+                    ));
+                }
+                checked.push(existing.to_path_buf());
+            }
+        }
+        self.select_package(&packages[0], true);
+        Ok(())
+    }
+
+    /// Parses a package's control file with placeholders substituted
+    fn read_control<'a>(&self, files: &'a DebFiles) -> Result<(&'a Path, Control), Error> {
+        let Some(control_path) = files.files.get(&FileType::Control) else {
+            return Err(fatal!(
+                kind: "MissingRequiredFile", file: "control";
+                "could not locate a Control file for package"
+            ));
+        };
+
+        let contents = read_text(control_path)?;
+        let mut conditions = Conditions::new(self, control_path);
+        let mut control = String::new();
+        for line in contents.lines().filter(|line| conditions.keep(line)) {
+            control.push_str(line);
+            control.push('\n');
+        }
+        conditions.finish().map_err(|err| fatal!("{err}"))?;
+
+        for (key, value) in self.replacements() {
+            if key.starts_with(CONTROL_PLACEHOLDER) {
+                continue;
+            }
+            control = control.replace(key, value);
+        }
+        Ok((control_path, self.parse_control(&control, control_path)?))
+    }
 
-        output.flush()
+    /// Parses the control of the current package, picking its paragraph from a debhelper style `debian/control`
+    fn parse_control(&self, contents: &str, input: &Path) -> Result<Control, Error> {
+        Control::parse_binary(contents, &self.package_name, self.architecture.short()).ok_or_else(
+            || {
+                fatal!(
+                    "control file: '{}' has no paragraph for package: '{}'",
+                    input.display(),
+                    self.package_name
+                )
+            },
+        )
+    }
+
+    /// Links `usr/share/doc/<package>` to the doc directory of the source package it was built from
+    fn write_doc_link(&self, source: &str) -> Result<PathBuf, Error> {
+        let link = self.get_file_type_path(FileType::Copyright);
+        fs::create_dir_all(
+            link.parent()
+                .expect("doc directories are within `usr/share/doc`"),
+        )?;
+        remove_link(&link)?;
+
+        symlink(Path::new(source), &link)?;
+        Ok(link)
+    }
+
+    /// Reads the `Package` field of a package's control file, with placeholders substituted
+    fn read_package_name(&self, files: &DebFiles) -> Result<String, Error> {
+        let (control_path, control) = self.read_control(files)?;
+
+        match control.get("Package") {
+            Some(name) => Ok(name.to_string()),
+            None => Err(fatal!(
+                "control file: '{}' is missing the 'Package' field",
+                control_path.display()
+            )),
+        }
     }
 }
 
-trait DebCollector {
-    fn conditional_insert(&mut self, entry: &DirEntry, dry_run: bool);
+/// The `index`th component of the upstream part of `version`, without any epoch or Debian revision, eg. `2`, `4` and
+/// `0` from `1:2.4.0-1`. A component the version does not have, eg. the patch of `1.2-3`, is empty
+fn version_part(version: &str, index: usize) -> &str {
+    let version = version
+        .split_once(':')
+        .map_or(version, |(_, version)| version);
+    let upstream = version
+        .rsplit_once('-')
+        .map_or(version, |(upstream, _)| upstream);
+    // A `+` or `~` starts build metadata, eg. from `--version-suffix`, which is not part of any component
+    upstream
+        .split(['.', '+', '~'])
+        .nth(index)
+        .unwrap_or_default()
 }
 
-impl DebCollector for DebFiles {
-    fn conditional_insert(&mut self, entry: &DirEntry, dry_run: bool) {
-        if let Some(deb_file) = entry.debian_file() {
-            if self.insert(deb_file, entry.path()).is_some() {
-                exit_err!("found more than 1 {deb_file:?} file")
-            }
+/// Reads a provided text file without a leading byte order mark. Invalid UTF-8 errors naming the offending byte
+fn read_text(path: &Path) -> Result<String, Error> {
+    let bytes = fs::read(path)?;
+    let start = if bytes.starts_with(UTF8_BOM) {
+        UTF8_BOM.len()
+    } else {
+        0
+    };
+
+    match std::str::from_utf8(&bytes[start..]) {
+        Ok(text) => Ok(text.to_string()),
+        Err(err) => Err(fatal!(
+            "invalid UTF-8 at byte offset: {} in: '{}'",
+            start + err.valid_up_to(),
+            path.display()
+        )),
+    }
+}
+
+/// Writes a staged file, gzip compressed when its staged name ends in `.gz`, eg. `changelog.Debian.gz`
+fn write_staged(path: &Path, contents: &[u8]) -> io::Result<()> {
+    if path.extension().is_none_or(|extension| extension != "gz") {
+        return fs::write(path, contents);
+    }
+    let mut gz = GzBuilder::new().write(fs::File::create(path)?, flate2::Compression::best());
+    gz.write_all(contents)?;
+    gz.finish().map(drop)
+}
+
+/// Reads a staged text file written by `write_staged`
+fn read_staged(path: &Path) -> io::Result<String> {
+    let mut contents = String::new();
+    if path.extension().is_some_and(|extension| extension == "gz") {
+        GzDecoder::new(BufReader::new(fs::File::open(path)?)).read_to_string(&mut contents)?;
+    } else {
+        fs::File::open(path)?.read_to_string(&mut contents)?;
+    }
+    Ok(contents)
+}
+
+/// Names of the `$Placeholder`s in `text`. Only CamelCase names are considered
+fn placeholder_names(text: &str) -> impl Iterator<Item = &str> {
+    text.match_indices('$').filter_map(|(i, _)| {
+        let name = text[i + 1..]
+            .split(|c: char| !c.is_ascii_alphanumeric())
+            .next()
+            .unwrap_or_default();
+
+        (name.starts_with(|c: char| c.is_ascii_uppercase())
+            && name.contains(|c: char| c.is_ascii_lowercase()))
+        .then_some(name)
+    })
+}
+
+/// Creates and removes an empty file in `dir`, permissions alone do not reveal a read-only mount
+fn probe_writable(dir: &Path) -> io::Result<()> {
+    let probe = dir.join(format!(".{PKG_NAME}-probe-{}", std::process::id()));
+    fs::File::create(&probe)?;
+    fs::remove_file(probe)
+}
+
+/// Removes a symlink left in place by a previous `--incremental` run so it can be recreated
+fn remove_link(link: &Path) -> io::Result<()> {
+    match fs::symlink_metadata(link) {
+        Ok(metadata) if metadata.is_symlink() => fs::remove_file(link),
+        _ => Ok(()),
+    }
+}
+
+/// Creates `link` pointing to `target`. Hosts without unix symlinks can only package links with the native `.deb`
+/// writer, which is handed the link instead
+fn symlink(target: &Path, link: &Path) -> Result<(), Error> {
+    #[cfg(unix)]
+    return Ok(std::os::unix::fs::symlink(target, link)?);
+    #[cfg(not(unix))]
+    {
+        let Some(deferred) = deb::DEFERRED_LINKS.get() else {
+            return Err(fatal!(
+                "symlink: '{}' can only be staged on unix hosts, --build can package it instead",
+                link.display()
+            ));
+        };
+        deferred
+            .lock()
+            .expect("never poisoned")
+            .push((link.to_path_buf(), target.to_path_buf()));
+        Ok(())
+    }
+}
+
+/// Decompresses a `.gz` or `.xz` binary into `output` marking it executable
+fn decompress_binary(input: &Path, extension: &str, output: &Path) -> Result<(), Error> {
+    let mut reader = BufReader::new(fs::File::open(input)?);
+    let mut writer = BufWriter::new(fs::File::create(output)?);
+
+    match extension {
+        "gz" => {
+            io::copy(&mut GzDecoder::new(reader), &mut writer)?;
+        }
+        "xz" => lzma_rs::xz_decompress(&mut reader, &mut writer).map_err(|err| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("failed to decompress '{}': {err}", input.display()),
+            )
+        })?,
+        _ => unreachable!("only called with one of `BINARY_COMPRESSIONS`"),
+    }
+    writer.flush()?;
+    drop(writer);
+
+    if !elf::is_elf(output)? {
+        fs::remove_file(output)?;
+        return Err(fatal!(
+            "decompressed binary: '{}' is not an ELF executable",
+            input.display()
+        ));
+    }
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(output, fs::Permissions::from_mode(0o755))?;
+    }
+
+    Ok(())
+}
+
+impl DebFiles {
+    fn new(prefer: Option<SearchDir>, icons: &[(String, FileType)], locale_desktop: bool) -> Self {
+        Self {
+            prefer,
+            icons: icons.iter().cloned().collect(),
+            locale_desktop,
+            ..Default::default()
+        }
+    }
+
+    fn conditional_insert(
+        &mut self,
+        entry: &DirEntry,
+        origin: Option<SearchDir>,
+        dry_run: bool,
+    ) -> Result<(), Error> {
+        let file_name = entry.file_name();
+        // Every file type is named in ASCII, a name that is not UTF-8 can never match one
+        let Some(file_name) = file_name.to_str() else {
+            warn!(
+                kind: "NonUtf8FileName", file: entry.path().display();
+                "skipped file with a non UTF-8 name: '{}'",
+                entry.path().display()
+            );
+            return Ok(());
+        };
+
+        if let Some(name) = placeholder_names(file_name).next() {
+            warn!(
+                "placeholder: '${name}' in file name: '{}' is never substituted, staged files are named \
+                automatically, eg. any Desktop file is staged as '<package>.desktop'",
+                entry.path().display()
+            )
+        }
+
+        if self.locale_desktop
+            && let Some(locale) = desktop::locale(file_name)
+        {
             if dry_run {
-                println!("Found {deb_file:?} file")
+                println!("Found Desktop translation for locale: {locale}")
+            }
+            self.desktop_locales.push(entry.path());
+            self.desktop_locales.sort();
+            return Ok(());
+        }
+
+        let mapped_icon = self.icons.get(file_name).map(|&icon| (icon, None));
+
+        let Some((mut deb_file, architecture)) =
+            mapped_icon.or_else(|| entry.debian_file()).or_else(|| {
+                let prefix = self.package_prefix.as_deref()?;
+                deb_files::parse_file_name(file_name.strip_prefix(prefix)?.strip_prefix('.')?)
+            })
+        else {
+            return Ok(());
+        };
+
+        // A plain `changelog` next to a `changelog.Debian` is the project's own changelog
+        let is_debian = |path: &Path| {
+            path.file_name()
+                .is_some_and(|name| name == DEBIAN_CHANGELOG)
+        };
+        if (deb_file, architecture) == (FileType::Changelog, None)
+            && let Some(existing) = self.files.get(&FileType::Changelog)
+            && is_debian(&entry.path()) != is_debian(existing)
+        {
+            if is_debian(&entry.path()) {
+                let upstream = self
+                    .files
+                    .remove(&FileType::Changelog)
+                    .expect("found above");
+                let origin = self.origins.remove(&(FileType::Changelog, None)).flatten();
+                self.files.insert(FileType::UpstreamChangelog, upstream);
+                self.origins
+                    .insert((FileType::UpstreamChangelog, None), origin);
+            } else {
+                deb_file = FileType::UpstreamChangelog;
+            }
+        }
+
+        let suffix = architecture
+            .map(|architecture| format!(" for {}", architecture.short()))
+            .unwrap_or_default();
+
+        if let Some(&existing) = self.origins.get(&(deb_file, architecture)) {
+            match self.prefer {
+                Some(prefer) if existing == Some(prefer) && origin != Some(prefer) => {
+                    if dry_run {
+                        println!(
+                            "Ignored {deb_file:?} file{suffix} at: '{}', preferring ~/{}",
+                            entry.path().display(),
+                            prefer.name()
+                        )
+                    }
+                    return Ok(());
+                }
+                Some(prefer) if origin == Some(prefer) && existing != Some(prefer) => {
+                    if dry_run {
+                        println!(
+                            "Preferred {deb_file:?} file{suffix} from ~/{}",
+                            prefer.name()
+                        )
+                    }
+                }
+                _ => return Err(fatal!("found more than 1 {deb_file:?} file{suffix}")),
+            }
+        } else if dry_run {
+            println!("Found {deb_file:?} file{suffix}")
+        }
+
+        self.origins.insert((deb_file, architecture), origin);
+        match architecture {
+            Some(architecture) => self
+                .arch_files
+                .insert((deb_file, architecture), entry.path()),
+            None => self.files.insert(deb_file, entry.path()),
+        };
+        Ok(())
+    }
+
+    fn insert_copyright_fragments(&mut self, dir: &Path, dry_run: bool) -> io::Result<()> {
+        for entry in fs::read_dir(dir)? {
+            let entry = entry?;
+            if entry.file_type()?.is_file() {
+                self.copyright_fragments.push(entry.path())
+            }
+        }
+        self.copyright_fragments.sort();
+
+        if dry_run {
+            println!(
+                "Found {} copyright fragment(s) in: '{}'",
+                self.copyright_fragments.len(),
+                dir.display()
+            )
+        }
+        Ok(())
+    }
+
+    /// Moves everything found in `other` into `self`. Errors with a description of the first file type both found
+    fn absorb(&mut self, other: DebFiles) -> Result<(), String> {
+        for (file_type, path) in other.files {
+            if self.files.insert(file_type, path).is_some() {
+                return Err(format!("{file_type:?} file"));
+            }
+        }
+        for ((file_type, architecture), path) in other.arch_files {
+            if self
+                .arch_files
+                .insert((file_type, architecture), path)
+                .is_some()
+            {
+                return Err(format!("{file_type:?} file for {}", architecture.short()));
+            }
+        }
+        self.copyright_fragments.extend(other.copyright_fragments);
+        self.copyright_fragments
+            .sort_by(|a, b| a.file_name().cmp(&b.file_name()));
+        self.desktop_locales.extend(other.desktop_locales);
+        self.desktop_locales
+            .sort_by(|a, b| a.file_name().cmp(&b.file_name()));
+        if let Some(dir) = other.user_templates {
+            if self.user_templates.is_some() {
+                return Err(format!("{} template directory", user_install::TEMPLATE_DIR));
+            }
+            self.user_templates = Some(dir)
+        }
+        Ok(())
+    }
+
+    /// Moves every file found in `authoritative` into `self`, replacing any file of the same type
+    fn overlay(&mut self, authoritative: DebFiles, dry_run: bool) {
+        let replaced = |found: bool, file_type: FileType| {
+            if found && dry_run {
+                println!("Replaced {file_type:?} file with the one from --debian-dir")
+            }
+        };
+        for (file_type, path) in authoritative.files {
+            replaced(self.files.insert(file_type, path).is_some(), file_type);
+            self.origins
+                .insert((file_type, None), Some(SearchDir::Debian));
+        }
+        for ((file_type, architecture), path) in authoritative.arch_files {
+            replaced(
+                self.arch_files
+                    .insert((file_type, architecture), path)
+                    .is_some(),
+                file_type,
+            );
+            self.origins
+                .insert((file_type, Some(architecture)), Some(SearchDir::Debian));
+        }
+        if !authoritative.copyright_fragments.is_empty() {
+            self.copyright_fragments = authoritative.copyright_fragments
+        }
+        if !authoritative.desktop_locales.is_empty() {
+            self.desktop_locales = authoritative.desktop_locales
+        }
+        if authoritative.user_templates.is_some() {
+            self.user_templates = authoritative.user_templates
+        }
+    }
+
+    fn contains(&self, file_type: FileType) -> bool {
+        self.files.contains_key(&file_type)
+            || self.arch_files.keys().any(|&(found, _)| found == file_type)
+    }
+
+    fn contains_for(&self, file_type: FileType, architecture: Architecture) -> bool {
+        self.files.contains_key(&file_type)
+            || self.arch_files.contains_key(&(file_type, architecture))
+    }
+
+    /// Every file found, including those restricted to other architectures
+    fn iter_all(&self) -> impl Iterator<Item = (FileType, &Path)> {
+        self.files
+            .iter()
+            .map(|(&file_type, path)| (file_type, path.as_path()))
+            .chain(
+                self.arch_files
+                    .iter()
+                    .map(|(&(file_type, _), path)| (file_type, path.as_path())),
+            )
+    }
+
+    /// The files to stage for `architecture`, preferring architecture suffixed files
+    fn for_architecture(&self, architecture: Architecture) -> HashMap<FileType, &Path> {
+        let mut files = self
+            .files
+            .iter()
+            .map(|(&file_type, path)| (file_type, path.as_path()))
+            .collect::<HashMap<_, _>>();
+
+        for (&(file_type, file_arch), path) in self.arch_files.iter() {
+            if file_arch == architecture {
+                files.insert(file_type, path);
             }
         }
+        files
     }
 }
 
-#[derive(Clone, Copy)]
-enum SearchDir {
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(crate) enum SearchDir {
     Assets,
     Build,
     Debian,
@@ -194,37 +1275,77 @@ enum SearchDir {
 
 impl PartialEq<SearchDir> for OsString {
     fn eq(&self, other: &SearchDir) -> bool {
-        self == match other {
+        self == other.name()
+    }
+}
+
+impl SearchDir {
+    pub(crate) fn name(self) -> &'static str {
+        match self {
             SearchDir::Assets => "assets",
             SearchDir::Build => "build",
             SearchDir::Debian => "debian",
         }
     }
-}
 
-impl SearchDir {
-    fn scan<P>(self, directory: P, deb_files: &mut DebFiles, dry_run: bool) -> io::Result<()>
+    pub(crate) fn from_name(name: &str) -> Option<Self> {
+        SEARCH_DIRS.into_iter().find(|dir| dir.name() == name)
+    }
+
+    /// Scans `directory` for debian files, never descending into any of the `skip` directories. Found files are
+    /// attributed to the top level search directory `origin`
+    fn scan<P>(
+        self,
+        directory: P,
+        origin: SearchDir,
+        deb_files: &mut DebFiles,
+        skip: &[PathBuf],
+        dry_run: bool,
+    ) -> Result<(), Error>
     where
         P: AsRef<Path>,
     {
-        for entry in fs::read_dir(directory)? {
+        for entry in fs::read_dir(&directory)? {
             let entry = entry?;
             let file_type = entry.file_type()?;
 
+            let max_files = MAX_FILES.load(Ordering::Relaxed);
+            if SCANNED.fetch_add(1, Ordering::Relaxed) >= max_files {
+                return Err(fatal!(
+                    "scanned more than {max_files} entries, stopped in: '{}', check the project path is correct or raise --max-files",
+                    directory.as_ref().display()
+                ));
+            }
+
+            if file_type.is_dir() && skip.contains(&entry.path()) {
+                continue;
+            }
+
             match self {
-                SearchDir::Assets | SearchDir::Debian if file_type.is_dir() => {
-                    self.scan(entry.path(), deb_files, dry_run)?
+                SearchDir::Debian
+                    if file_type.is_dir() && entry.file_name() == copyright::FRAGMENT_DIR =>
+                {
+                    deb_files.insert_copyright_fragments(&entry.path(), dry_run)?
                 }
-                SearchDir::Build if file_type.is_dir() => {
-                    let file_name = entry.file_name();
-                    if !dry_run && file_name == TEMP_DIR {
-                        fs::remove_dir_all(entry.path())?;
-                        println!("Reset contents of ~\\build\\tmp")
-                    } else if file_name == SearchDir::Debian {
-                        SearchDir::Debian.scan(entry.path(), deb_files, dry_run)?
+                SearchDir::Debian
+                    if file_type.is_dir() && entry.file_name() == user_install::TEMPLATE_DIR =>
+                {
+                    if dry_run {
+                        println!("Found user templates in: '{}'", entry.path().display())
                     }
+                    deb_files.user_templates = Some(entry.path())
+                }
+                SearchDir::Assets | SearchDir::Debian if file_type.is_dir() => {
+                    self.scan(entry.path(), origin, deb_files, skip, dry_run)?
+                }
+                SearchDir::Build
+                    if file_type.is_dir() && entry.file_name() == SearchDir::Debian =>
+                {
+                    SearchDir::Debian.scan(entry.path(), origin, deb_files, skip, dry_run)?
+                }
+                _ if file_type.is_file() => {
+                    deb_files.conditional_insert(&entry, Some(origin), dry_run)?
                 }
-                _ if file_type.is_file() => deb_files.conditional_insert(&entry, dry_run),
                 _ => (),
             }
         }
@@ -232,70 +1353,627 @@ impl SearchDir {
     }
 }
 
+/// How a run goes on once `Forge::from` returns, `main` decides when to exit
+pub enum Run {
+    /// Files were discovered and validated, `Forge::forge` stages them
+    Stage(Box<Forge>),
+    /// A mode that ends before staging, eg. `--dry-run` or `--list-found`, finished with this exit code
+    Exit(i32),
+}
+
 impl Forge {
-    pub fn from(args: Args) -> io::Result<Self> {
-        let dry_run = args.dry_run;
-        let vars = Variables::from(args)?;
-
-        let mut deb_files = DebFiles::new();
-
-        let binary_path = vars.get_binary_path();
-        if !binary_path.exists() {
-            exit_err!(
-                "failed to find Binary: '{}' at: '{}'",
-                vars.binary_name,
-                binary_path
-                    .parent()
-                    .expect("`get_binary_path` gives a location within a directory")
-                    .display()
-            )
+    /// Runs the discovery phase, `Variables::discover`, and the validation phase, `Variables::validate_packages`,
+    /// which `--dry-run` and `--check` end after. Nothing is written until `forge`
+    pub fn from(mut args: Args) -> Result<Run, Error> {
+        let (dry_run, warnings_as_errors) = (args.dry_run, args.warnings_as_errors);
+        let check = args.check;
+        if args.dump_config {
+            dump::print(args)?;
+            return Ok(Run::Exit(0));
         }
-
-        deb_files.insert(FileType::Binary, binary_path);
-        if dry_run {
-            println!("Found Binary file")
+        if args.init {
+            init::scaffold(&args.project_dir)?;
+            return Ok(Run::Exit(0));
+        }
+        if let Some(timeout) = args.tool_timeout {
+            tool::TOOL_TIMEOUT
+                .set(timeout)
+                .expect("`Forge::from` is only called once");
+        }
+        let changelog_from_git = args.changelog_from_git;
+        let version_from_changelog = args.version_from_changelog;
+        let list_found = args.list_found;
+        let emit = args.emit;
+        let generate_copyright = args.generate_copyright;
+        let print_path = args.print_path;
+        let relocate = args.relocate.take();
+        let binary_sha256 = args.binary_sha256.take();
+        let package_controls = std::mem::take(&mut args.packages);
+        let prefer_dir = args.prefer_dir;
+        let locale_desktop = args.locale_desktop;
+        let search_dirs = std::mem::take(&mut args.search_dirs);
+        let debian_dir = args.debian_dir.take();
+        MAX_FILES.store(args.max_files, Ordering::Relaxed);
+        let orig_tarball = args.orig_tarball;
+        let excludes = std::mem::take(&mut args.excludes);
+        let quiet = args.quiet;
+        let check_ownership = args.check_ownership;
+        let shellcheck = args.shellcheck;
+        let fix_installed_size = args.fix_installed_size;
+        let allow_missing = std::mem::take(&mut args.allow_missing);
+        let only = std::mem::take(&mut args.only);
+        let package_name_given = args.package_name.is_some();
+        let require = std::mem::take(&mut args.require);
+        let skip = std::mem::take(&mut args.skip);
+        let summary_json = args.summary_json.take();
+        let doc_link = args.doc_link;
+        let incremental = args.incremental;
+        let relative_symlinks = args.relative_symlinks;
+        let build = args.build;
+        let packages_index = args.packages_index.take();
+        let changes = args.changes;
+        let verify_deb = args.verify_deb;
+        let (clean, keep_staging) = (args.clean, args.keep_staging);
+        let compress_binary = args.compress_binary;
+        let max_size = args.max_size;
+        if let Some(DebBuilder::Native(_)) = build {
+            let _ = deb::DEFERRED_LINKS.set(Default::default());
+        }
+        if let Some(DebBuilder::DpkgDeb(_, true)) = build
+            && !tool::on_path("fakeroot")
+        {
+            return Err(fatal!(
+                "--fakeroot was requested but fakeroot was not found on PATH"
+            ));
         }
+        if verify_deb && !tool::on_path("dpkg-deb") {
+            return Err(fatal!(
+                "--verify-deb was requested but dpkg-deb was not found on PATH"
+            ));
+        }
+        let mut vars = Variables::from(args)?;
 
-        for entry in fs::read_dir(&vars.project_dir)? {
-            let entry = entry?;
-            let file_type = entry.file_type()?;
+        if let Some(file_type) = print_path {
+            println!("{}", vars.get_output_path(file_type).display());
+            return Ok(Run::Exit(0));
+        }
 
-            if file_type.is_dir() {
-                let file_name = entry.file_name();
-                if let Some(search_dir) = SEARCH_DIRS.iter().find(|&&valid| file_name == valid) {
-                    search_dir.scan(entry.path(), &mut deb_files, dry_run)?;
+        // A utility mode on an existing staging, no files are discovered
+        if let Some((old, new)) = relocate {
+            for &architecture in vars.config.targets.clone().iter() {
+                vars.architecture = architecture;
+                let stage_root = vars.get_stage_root();
+                relocate::relocate(&stage_root, &old, &new)?;
+                if !quiet {
+                    println!(
+                        "Relocated: '{}' to: '{}' in: '{}'",
+                        old.display(),
+                        new.display(),
+                        stage_root.display()
+                    )
                 }
-            } else if file_type.is_file() {
-                deb_files.conditional_insert(&entry, dry_run)
             }
+            return Ok(Run::Exit(0));
         }
 
-        for required in REQUIRED_DEB_FILES.iter() {
-            if !deb_files.contains_key(required) {
-                exit_err!("could not locate a {required:?} file")
-            }
+        let discovered = vars.discover(Discovery {
+            binary_sha256,
+            package_controls,
+            prefer_dir,
+            locale_desktop,
+            search_dirs,
+            debian_dir,
+            package_name_given,
+            dry_run,
+        })?;
+
+        // An introspection mode, nothing found is validated or staged. What could not be found is listed too
+        if list_found {
+            discover::list(&discovered, &vars.project_dir);
+            return Ok(Run::Exit(0));
+        }
+        vars.check_discovered(&discovered)?;
+        let Discovered {
+            binaries,
+            mut packages,
+            ..
+        } = discovered;
+
+        if version_from_changelog {
+            vars.version = vars.version_from_changelog(&packages[0].files)?;
+        }
+        vars.check_user_templates(&packages)?;
+        if vars.reproducible {
+            vars.warn_environment_references(&packages)?;
+        }
+        vars.warn_missing_icons(&packages);
+        if doc_link {
+            vars.link_docs(&mut packages, dry_run)?;
+        }
+        vars.generate_docs(&packages, changelog_from_git, generate_copyright, dry_run)?;
+
+        // Renders a single file of the primary package, nothing is staged
+        if let Some(file_type) = emit {
+            vars.emit(file_type, &packages[0])?;
+            return Ok(Run::Exit(0));
         }
 
+        vars.check_required(
+            &packages,
+            Requirements {
+                require: &require,
+                allow_missing: &allow_missing,
+                only: &only,
+                skip: &skip,
+                generate_copyright,
+            },
+        )?;
+
+        if check {
+            let problems = vars.validate_packages(&packages)?;
+            return Ok(Run::Exit(validate::report_check(&problems)));
+        }
         if dry_run {
-            println!("{PKG_NAME}: Success valid project file structure");
-            std::process::exit(0)
+            let problems = vars.validate_packages(&packages)?;
+            vars.report_dry_run(&problems, &packages, &binaries, compress_binary, build)?;
+
+            let warning_count = warnings::count();
+            if warnings_as_errors && warning_count > 0 {
+                return Err(fatal!("dry run emitted {warning_count} warning(s)"));
+            }
+
+            println!(
+                "{PKG_NAME}: {}",
+                color::stdout(color::GREEN, "Success valid project file structure")
+            );
+            return Ok(Run::Exit(0));
         }
 
-        Ok(Self {
+        let architectures = binaries
+            .iter()
+            .map(|&(architecture, _)| architecture)
+            .collect::<Vec<_>>();
+        vars.check_writable(&packages, &architectures)?;
+
+        // The only removal outside of a restaged tree, never reached by `--dry-run` or `--check`
+        if clean {
+            vars.clean_temp_dir()?
+        }
+
+        if vars.dest_root.is_some() && binaries.len() > 1 {
+            return Err(fatal!(
+                "--dest-root can only stage a single target, pass 1 with --target"
+            ));
+        }
+
+        vars.architecture = binaries[0].0;
+        let manifest = incremental.then(|| Manifest::load(&vars.project_dir));
+        Ok(Run::Stage(Box::new(Self {
             vars,
-            files: deb_files,
-        })
+            packages,
+            binaries,
+            orig_tarball: orig_tarball.then_some(excludes),
+            quiet,
+            check_ownership,
+            shellcheck,
+            fix_installed_size,
+            manifest,
+            relative_symlinks,
+            build,
+            packages_index,
+            changes,
+            verify_deb,
+            keep_staging,
+            compress_binary,
+            max_size,
+            only,
+            skip,
+            summary_json,
+        })))
     }
 
-    pub fn forge(self) -> io::Result<()> {
-        for (&file, path) in self.files.iter() {
-            self.vars.write_file(file, path)?
+    /// Whether `file_type` is staged by this run
+    fn stages(&self, file_type: FileType) -> bool {
+        (self.only.is_empty() || self.only.contains(&file_type)) && !self.skip.contains(&file_type)
+    }
+
+    /// The staging phase, writes every discovered file into each package's staging tree and builds it when asked to
+    pub fn forge(mut self) -> Result<(), Error> {
+        let (mut file_count, mut unchanged, mut skipped_metadata) = (0, 0, 0);
+        let mut skipped_types = Vec::new();
+        let mut reports = Vec::new();
+
+        for &(architecture, ref binary_path) in self.binaries.iter() {
+            self.vars.architecture = architecture;
+
+            for (i, package) in self.packages.iter().enumerate() {
+                let primary = i == 0;
+                self.vars.select_package(package, primary);
+                self.vars.load_control_fields(&package.files)?;
+
+                let final_root = self.vars.get_stage_root();
+                let mut summary = Summary::default();
+
+                // Only the tree about to be restaged is replaced, anything else in `build/tmp` is left alone. A full
+                // restage is staged beside it and renamed into place once complete, so a failed run never leaves a
+                // partial tree under the final name. A `--dest-root` is the user's directory, it is never removed
+                let atomic = !self.keep_staging
+                    && self.only.is_empty()
+                    && self.manifest.is_none()
+                    && self.vars.dest_root.is_none();
+                self.vars.partial = atomic;
+                let stage_root = self.vars.get_stage_root();
+                if atomic {
+                    // Left behind by a run that was killed before it could remove it
+                    if stage_root.is_dir() {
+                        fs::remove_dir_all(&stage_root)?
+                    }
+                    error::remove_on_exit(Some(stage_root.clone()));
+                }
+
+                let mut files = package.files.for_architecture(architecture);
+                // Substituted into the control file rather than staged as a file
+                self.vars
+                    .load_substvars(files.remove(&FileType::Substvars))?;
+                if self.vars.dest_root.is_some() {
+                    let metadata = files.len();
+                    files.retain(|file, _| file.is_installed() || *file == FileType::Links);
+                    skipped_metadata += metadata - files.len();
+                }
+                for file in files.keys().filter(|file| self.skip.contains(file)) {
+                    if !skipped_types.contains(file) {
+                        skipped_types.push(*file)
+                    }
+                }
+                files.retain(|&file, _| self.stages(file));
+                if !(primary && self.vars.wrapper) {
+                    files.remove(&FileType::Wrapper);
+                }
+                // The doc link replaces the doc directory, only a full staging writes it
+                if let Some(source) = package.doc_link.as_deref()
+                    && self.only.is_empty()
+                {
+                    // The linked source package ships the docs, a copy here would conflict with it
+                    files.retain(|&file, _| {
+                        !matches!(
+                            file,
+                            FileType::Changelog
+                                | FileType::UpstreamChangelog
+                                | FileType::ReadmeDebian
+                                | FileType::Copyright
+                        )
+                    });
+                    let link = self.vars.write_doc_link(source)?;
+                    summary.record(&stage_root, &link)?
+                }
+                // Links are created in the staged tree rather than staged as a file
+                if let Some(links) = files.remove(&FileType::Links) {
+                    for link in self.vars.write_links(links, self.relative_symlinks)? {
+                        summary.record(&stage_root, &link)?
+                    }
+                }
+                for (&file, path) in files.iter() {
+                    let output = self.vars.get_output_path(file);
+                    let fresh = match self.manifest.as_mut() {
+                        Some(manifest) => manifest.is_fresh(&self.vars, file, path, &output)?,
+                        None => false,
+                    };
+                    if fresh {
+                        unchanged += 1
+                    } else {
+                        self.vars.write_file(file, path)?
+                    }
+                    // A compressible doc's name is only known once it is written
+                    summary.record(&stage_root, &self.vars.get_output_path(file))?
+                }
+                file_count += files.len();
+
+                if primary {
+                    if let Some(entry) = self.vars.changelog_entry.as_deref()
+                        && package.doc_link.is_none()
+                        && !files.contains_key(&FileType::Changelog)
+                        && self.stages(FileType::Changelog)
+                    {
+                        self.vars.write_generated_changelog(entry)?;
+                        summary
+                            .record(&stage_root, &self.vars.get_output_path(FileType::Changelog))?
+                    }
+                    if let Some(copyright) = self.vars.generated_copyright.as_deref()
+                        && package.doc_link.is_none()
+                        && !files.contains_key(&FileType::Copyright)
+                        && self.stages(FileType::Copyright)
+                    {
+                        self.vars.write_generated_copyright(copyright)?;
+                        summary
+                            .record(&stage_root, &self.vars.get_output_path(FileType::Copyright))?
+                    }
+                    if self.stages(FileType::Binary) {
+                        let output = self.vars.get_output_path(FileType::Binary);
+                        let fresh = match self.manifest.as_mut() {
+                            Some(manifest) => manifest.is_fresh(
+                                &self.vars,
+                                FileType::Binary,
+                                binary_path,
+                                &output,
+                            )?,
+                            None => false,
+                        };
+                        if fresh {
+                            unchanged += 1
+                        } else {
+                            self.vars.write_file(FileType::Binary, binary_path)?
+                        }
+                        summary.record(&stage_root, &output)?;
+                        if elf::is_elf(&output)? {
+                            summary.build_id = elf::build_id(&output)?
+                        }
+                        self.vars.check_linked_libraries()?;
+                        // A fresh binary under `--incremental` was already compressed by the previous run
+                        if self.compress_binary && !fresh {
+                            self.compress_binary = upx::compress(&output, self.quiet)?;
+                        }
+
+                        if let Some(opt_dir) = self.vars.get_opt_dir() {
+                            let link = self.vars.write_opt_symlink(&opt_dir)?;
+                            summary.record(&stage_root, &link)?
+                        }
+                    }
+                    if self.vars.user_install
+                        && let Some(dir) = package.files.user_templates.as_deref()
+                    {
+                        for staged in self.vars.stage_user_templates(dir)? {
+                            summary.record(&stage_root, &staged)?
+                        }
+                    }
+                    if let Some(fragment) = self.vars.postinst_fragment()
+                        && self.vars.dest_root.is_none()
+                        && !files.contains_key(&FileType::PostInst)
+                        && self.stages(FileType::PostInst)
+                    {
+                        self.vars.write_generated_postinst(&fragment)?;
+                        summary
+                            .record(&stage_root, &self.vars.get_output_path(FileType::PostInst))?
+                    }
+                }
+
+                // The staged copyright file is already recorded, merging only replaces its contents
+                if !package.files.copyright_fragments.is_empty()
+                    && package.doc_link.is_none()
+                    && self.stages(FileType::Copyright)
+                {
+                    let base = match files.get(&FileType::Copyright) {
+                        Some(path) => Some(self.vars.render_text(FileType::Copyright, path)?),
+                        None if primary => self.vars.generated_copyright.clone(),
+                        None => None,
+                    };
+                    if let Some(base) = base {
+                        self.vars
+                            .write_merged_copyright(&base, &package.files.copyright_fragments)?
+                    }
+                }
+
+                if !package.files.desktop_locales.is_empty() && self.stages(FileType::Desktop) {
+                    match files.get(&FileType::Desktop) {
+                        Some(base) => self
+                            .vars
+                            .write_merged_desktop(base, &package.files.desktop_locales)?,
+                        None => warn!(
+                            "found Desktop translations but no Desktop file for package: '{}'",
+                            package.name
+                        ),
+                    }
+                }
+
+                let control_path = self.vars.get_output_path(FileType::Control);
+                if control_path.is_file() {
+                    control::check_installed_size(
+                        &package.name,
+                        &control_path,
+                        &stage_root,
+                        self.fix_installed_size,
+                        self.quiet,
+                    )?
+                }
+
+                if self.check_ownership {
+                    self.check_ownership = ownership::check(&stage_root, &package.name)?;
+                }
+                if self.shellcheck {
+                    self.shellcheck = shellcheck::check(&stage_root, self.vars.strict)?;
+                }
+                // A built package is held to the budget by its `.deb` instead
+                if let Some(budget) = self.max_size
+                    && self.build.is_none()
+                {
+                    summary::check_budget(
+                        &format!("package: '{}'", package.name),
+                        summary::installed_bytes(&stage_root)?,
+                        budget,
+                    )?
+                }
+
+                if atomic {
+                    if final_root.is_dir() {
+                        fs::remove_dir_all(&final_root)?
+                    }
+                    fs::rename(&stage_root, &final_root)?;
+                    error::remove_on_exit(None);
+                    self.vars.partial = false;
+                }
+                let stage_root = final_root;
+
+                if self.summary_json.is_some() {
+                    reports.push(PackageReport {
+                        package: package.name.clone(),
+                        architecture: architecture.short(),
+                        files: summary.file_count(),
+                        installed_bytes: summary::installed_bytes(&stage_root)?,
+                        build_id: summary.build_id.clone(),
+                        artifact: None,
+                    })
+                }
+
+                if !self.quiet {
+                    summary.print(&stage_root)
+                }
+            }
+        }
+        file_count /= self.binaries.len();
+
+        if self.skip.contains(&FileType::Binary) {
+            skipped_types.push(FileType::Binary)
+        }
+        if !skipped_types.is_empty() && !self.quiet {
+            println!(
+                "{PKG_NAME}: Skipped file type(s): {}",
+                skipped_types
+                    .iter()
+                    .map(|file_type| format!("{file_type:?}"))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )
         }
 
+        if skipped_metadata > 0 && !self.quiet {
+            println!(
+                "{PKG_NAME}: Skipped {skipped_metadata} control file(s) and maintainer script(s), --dest-root only \
+                stages installed files"
+            )
+        }
+
+        if let Some(manifest) = self.manifest.take() {
+            manifest.save()?;
+            if !self.quiet {
+                println!("{PKG_NAME}: Skipped {unchanged} unchanged file(s)")
+            }
+        }
+
+        // Built once staging is complete so `--incremental` has removed stale outputs
+        if let Some(builder) = self.build {
+            let mut stanzas = Vec::new();
+            for &(architecture, _) in self.binaries.iter() {
+                self.vars.architecture = architecture;
+                let (mut built, mut changelog) = (Vec::new(), None);
+                for (i, package) in self.packages.iter().enumerate() {
+                    self.vars.select_package(package, i == 0);
+                    let deb = self.vars.write_deb(builder)?;
+                    if let Some(budget) = self.max_size {
+                        summary::check_budget(
+                            &format!("'{}'", deb.display()),
+                            fs::metadata(&deb)?.len(),
+                            budget,
+                        )?
+                    }
+                    if !self.quiet {
+                        println!("Built package: '{}'", deb.display())
+                    }
+                    if self.verify_deb {
+                        let control =
+                            fs::read_to_string(self.vars.get_output_path(FileType::Control))?;
+                        verify::verify(
+                            &deb,
+                            &self.vars.get_stage_root(),
+                            &Control::parse(&control),
+                            self.quiet,
+                        )?
+                    }
+                    if let Some(report) = reports.iter_mut().find(|report| {
+                        report.package == package.name
+                            && report.architecture == architecture.short()
+                    }) {
+                        report.artifact = Some(deb.clone())
+                    }
+                    if self.packages_index.is_some() {
+                        stanzas.push(self.vars.packages_stanza(&deb)?)
+                    }
+                    if self.changes {
+                        let control =
+                            fs::read_to_string(self.vars.get_output_path(FileType::Control))?;
+                        built.push((deb, Control::parse(&control)));
+                        if i == 0 {
+                            changelog =
+                                read_staged(&self.vars.get_output_path(FileType::Changelog)).ok();
+                        }
+                    }
+                }
+                if self.changes {
+                    let changes = self.vars.write_changes(&built, changelog.as_deref())?;
+                    if !self.quiet {
+                        println!("Wrote changes file: '{}'", changes.display())
+                    }
+                }
+            }
+            if let Some(output) = self.packages_index.as_deref()
+                && let Some(index) = index::write(&stanzas, output)?
+                && !self.quiet
+            {
+                println!("Wrote packages index: '{}'", index.display())
+            }
+        }
+
+        if let Some(excludes) = self.orig_tarball.as_deref() {
+            self.vars.package_name.clone_from(&self.packages[0].name);
+            self.vars.write_orig_tarball(excludes)?
+        }
+
+        if let Some(output) = self.summary_json.as_deref() {
+            let warnings = warnings::messages();
+            if let Some(path) = report::write(output, &self.vars.version, &reports, &warnings)?
+                && !self.quiet
+            {
+                println!("Wrote summary: '{}'", path.display())
+            }
+        }
+
+        if self.quiet {
+            return Ok(());
+        }
+
+        if self.packages.len() > 1 {
+            println!(
+                "{PKG_NAME}: Staged packages: {}",
+                self.packages
+                    .iter()
+                    .map(|package| package.name.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            );
+        }
+
+        let targets = if self.binaries.len() > 1 {
+            format!(" for {} targets", self.binaries.len())
+        } else {
+            String::new()
+        };
+        let binary = match self.stages(FileType::Binary) {
+            true => ", and project binary",
+            false => "",
+        };
         println!(
-            "{PKG_NAME}: Successfully imported {} files, and project binary",
-            self.files.len() - 1
+            "{PKG_NAME}: {}",
+            color::stdout(
+                color::GREEN,
+                format_args!("Successfully imported {file_count} files{binary}{targets}")
+            )
         );
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn version_part_drops_revision() {
+        let parts = |version| {
+            (0..3)
+                .map(|index| version_part(version, index))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(parts("1.2-3"), ["1", "2", ""]);
+        assert_eq!(parts("1:1.2.3-1"), ["1", "2", "3"]);
+        assert_eq!(parts("1.2.3+build123"), ["1", "2", "3"]);
+        assert_eq!(parts("7"), ["7", "", ""]);
+    }
+}
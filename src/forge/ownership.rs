@@ -0,0 +1,74 @@
+use std::{fs, io, path::Path, process::Command};
+
+use super::tool;
+use crate::warn;
+
+/// Collects the install paths of every file staged under `dir`, skipping the `DEBIAN` control directory
+pub(super) fn install_paths(
+    stage_root: &Path,
+    dir: &Path,
+    paths: &mut Vec<String>,
+) -> io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let path = entry.path();
+
+        if entry.file_type()?.is_dir() {
+            if dir == stage_root && entry.file_name() == "DEBIAN" {
+                continue;
+            }
+            install_paths(stage_root, &path, paths)?;
+            continue;
+        }
+
+        let relative = path
+            .strip_prefix(stage_root)
+            .expect("`read_dir` yields paths within `dir`");
+        paths.push(format!("/{}", relative.to_string_lossy()));
+    }
+    Ok(())
+}
+
+/// Warns about staged files whose install path is already owned by another installed package according to
+/// `dpkg -S`. Returns `false` when dpkg could not be run so the check can be skipped for the remaining packages
+pub(super) fn check(stage_root: &Path, package_name: &str) -> io::Result<bool> {
+    let mut paths = Vec::new();
+    install_paths(stage_root, stage_root, &mut paths)?;
+    if paths.is_empty() {
+        return Ok(true);
+    }
+    paths.sort();
+
+    let output = match tool::output(Command::new("dpkg").arg("-S").args(&paths)) {
+        Ok(output) => output,
+        Err(err) if err.kind() == io::ErrorKind::TimedOut => return Err(err),
+        Err(err) => {
+            warn!("failed to run dpkg, skipping --check-ownership: {err}");
+            return Ok(false);
+        }
+    };
+
+    // Paths dpkg knows nothing about are reported on stderr with a non zero exit, only stdout lists owners
+    for line in String::from_utf8_lossy(&output.stdout).lines() {
+        if line.starts_with("diversion by") {
+            continue;
+        }
+        let Some((owners, path)) = line.split_once(": ") else {
+            continue;
+        };
+
+        let owners = owners
+            .split(", ")
+            .map(|owner| owner.split_once(':').map_or(owner, |(name, _)| name))
+            .filter(|&owner| owner != package_name)
+            .collect::<Vec<_>>();
+
+        if !owners.is_empty() {
+            warn!(
+                "'{path}' staged by: '{package_name}' is already owned by installed package: {}",
+                owners.join(", ")
+            )
+        }
+    }
+    Ok(true)
+}
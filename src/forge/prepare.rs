@@ -0,0 +1,116 @@
+use super::{
+    FileType, Package, TOML, Variables, environment, error::Error, read_text, user_install,
+};
+use crate::{fatal, warn};
+
+impl Variables {
+    /// `--user-install` requires the primary package's `debian/user` templates, templates found without it are never
+    /// staged
+    pub(super) fn check_user_templates(&self, packages: &[Package]) -> Result<(), Error> {
+        if self.user_install && packages[0].files.user_templates.is_none() {
+            return Err(fatal!(
+                "--user-install was requested but no debian/{} directory was found",
+                user_install::TEMPLATE_DIR
+            ));
+        }
+        if !self.user_install
+            && let Some(dir) = packages
+                .iter()
+                .find_map(|package| package.files.user_templates.as_deref())
+        {
+            warn!(
+                "found user templates in: '{}', they are only staged with --user-install",
+                dir.display()
+            )
+        }
+        Ok(())
+    }
+
+    /// Warns about each `${env:VAR}` a rendered file references, for `--reproducible`
+    pub(super) fn warn_environment_references(&self, packages: &[Package]) -> Result<(), Error> {
+        for (file_type, path) in packages.iter().flat_map(|package| package.files.iter_all()) {
+            if !self.is_rendered(file_type, path) {
+                continue;
+            }
+            for name in environment::references(&read_text(path)?) {
+                warn!(
+                    "'${{env:{name}}}' in: '{}' makes the --reproducible output depend on the build environment",
+                    path.display()
+                )
+            }
+        }
+        Ok(())
+    }
+
+    /// Warns about each file name mapped to an icon size that no package provides
+    pub(super) fn warn_missing_icons(&self, packages: &[Package]) {
+        for (file_name, icon) in self.config.icons.iter() {
+            if !packages.iter().any(|package| {
+                package.files.iter_all().any(|(_, path)| {
+                    path.file_name()
+                        .is_some_and(|name| name == file_name.as_str())
+                })
+            }) {
+                warn!("icon: '{file_name}' mapped to {icon:?} was not found")
+            }
+        }
+    }
+
+    /// Links the doc directory of each package whose control file names a different `Source`, for `--doc-link`
+    pub(super) fn link_docs(&self, packages: &mut [Package], dry_run: bool) -> Result<(), Error> {
+        for package in packages.iter_mut() {
+            let (_, control) = self.read_control(&package.files)?;
+            let source = control.get("Source").map(|source| {
+                // Binary control files may record the source version, eg. `Source: name (1.0-1)`
+                source.split_whitespace().next().unwrap_or_default()
+            });
+            if let Some(source) = source.filter(|&source| source != package.name) {
+                if dry_run {
+                    println!(
+                        "Linking doc directory of: '{}' to: '{source}'",
+                        package.name
+                    )
+                }
+                package.doc_link = Some(source.to_string());
+            }
+        }
+        if packages.iter().all(|package| package.doc_link.is_none()) {
+            warn!(
+                "--doc-link found no control file with a 'Source' that differs from its 'Package'"
+            )
+        }
+        Ok(())
+    }
+
+    /// Generates the changelog entry of `--changelog-from-git` and, when the primary package does not provide one, the
+    /// copyright file of `--generate-copyright`
+    pub(super) fn generate_docs(
+        &mut self,
+        packages: &[Package],
+        changelog_from_git: Option<bool>,
+        generate_copyright: bool,
+        dry_run: bool,
+    ) -> Result<(), Error> {
+        if let Some(group) = changelog_from_git {
+            let entry = self.git_changelog_entry(group)?;
+            if dry_run {
+                println!("Generated changelog entry:\n{entry}")
+            }
+            self.changelog_entry = Some(entry);
+        }
+
+        if generate_copyright && !packages[0].files.contains(FileType::Copyright) {
+            self.package_name.clone_from(&packages[0].name);
+            let Some(copyright) = self.generated_copyright() else {
+                return Err(fatal!(
+                    "--generate-copyright requires 'license' and 'authors' in {TOML}"
+                ));
+            };
+            if dry_run {
+                println!("Generated copyright:\n{copyright}")
+            }
+            self.generated_copyright = Some(copyright);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,83 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use super::error::Error;
+use crate::fatal;
+
+/// Destinations under `dest` that an entry of `source` would overwrite. A directory only collides with a file, 2
+/// directories are merged
+fn collisions(source: &Path, dest: &Path, found: &mut Vec<PathBuf>) -> io::Result<()> {
+    for entry in fs::read_dir(source)? {
+        let entry = entry?;
+        let target = dest.join(entry.file_name());
+        let Ok(existing) = fs::symlink_metadata(&target) else {
+            continue;
+        };
+        if entry.file_type()?.is_dir() && existing.is_dir() {
+            collisions(&entry.path(), &target, found)?
+        } else {
+            found.push(target)
+        }
+    }
+    Ok(())
+}
+
+/// Moves every entry of `source` into `dest`, merging directories, and removes `source` once it is empty
+fn move_tree(source: &Path, dest: &Path) -> io::Result<()> {
+    fs::create_dir_all(dest)?;
+    for entry in fs::read_dir(source)? {
+        let entry = entry?;
+        let target = dest.join(entry.file_name());
+        if entry.file_type()?.is_dir() && target.is_dir() {
+            move_tree(&entry.path(), &target)?
+        } else {
+            fs::rename(entry.path(), &target)?
+        }
+    }
+    // `dest` may be an ancestor of `source` that now holds some of its entries
+    if fs::read_dir(source)?.next().is_none() {
+        fs::remove_dir(source)?;
+    }
+    Ok(())
+}
+
+/// Re-roots the part of a staged tree under `old` at `new`, both relative to `stage_root`, eg. `usr/local` to `usr`.
+/// Nothing is moved when any file would be overwritten
+pub(super) fn relocate(stage_root: &Path, old: &Path, new: &Path) -> Result<(), Error> {
+    let source = stage_root.join(old);
+    if !source.is_dir() {
+        return Err(fatal!(
+            "--relocate found nothing staged at: '{}'",
+            source.display()
+        ));
+    }
+    if new.starts_with(old) {
+        return Err(fatal!(
+            "--relocate can not move: '{}' into itself: '{}'",
+            old.display(),
+            new.display()
+        ));
+    }
+
+    let dest = stage_root.join(new);
+    let mut found = Vec::new();
+    if dest.is_dir() {
+        collisions(&source, &dest, &mut found)?;
+    } else if dest.exists() {
+        found.push(dest.clone());
+    }
+    if !found.is_empty() {
+        return Err(fatal!(
+            "--relocate would overwrite:\n    {}",
+            found
+                .iter()
+                .map(|path| path.display().to_string())
+                .collect::<Vec<_>>()
+                .join("\n    ")
+        ));
+    }
+
+    Ok(move_tree(&source, &dest)?)
+}
@@ -0,0 +1,85 @@
+use std::{fmt::Write as _, fs, io, path::PathBuf};
+
+use super::error::json_string;
+
+/// What a run produced for 1 package on 1 architecture, reported by `--summary-json`
+pub(super) struct PackageReport {
+    pub(super) package: String,
+    pub(super) architecture: &'static str,
+    pub(super) files: usize,
+    pub(super) installed_bytes: u64,
+    pub(super) build_id: Option<String>,
+    /// The built `.deb`, `None` when only staged
+    pub(super) artifact: Option<PathBuf>,
+}
+
+fn optional(value: Option<&str>) -> String {
+    value.map_or_else(|| String::from("null"), json_string)
+}
+
+fn render(version: &str, packages: &[PackageReport], warnings: &[String]) -> String {
+    let mut json = format!(
+        "{{\n  \"version\": {},\n  \"packages\": [",
+        json_string(version)
+    );
+    for (i, report) in packages.iter().enumerate() {
+        let artifact = report
+            .artifact
+            .as_ref()
+            .map(|path| path.display().to_string());
+        let _ = write!(
+            json,
+            "{}\n    {{ \"package\": {}, \"architecture\": {}, \"files\": {}, \"installed_size\": {}, \
+            \"build_id\": {}, \"artifact\": {} }}",
+            if i == 0 { "" } else { "," },
+            json_string(&report.package),
+            json_string(report.architecture),
+            report.files,
+            report.installed_bytes,
+            optional(report.build_id.as_deref()),
+            optional(artifact.as_deref()),
+        );
+    }
+    json.push_str(if packages.is_empty() {
+        "],\n"
+    } else {
+        "\n  ],\n"
+    });
+
+    json.push_str("  \"warnings\": [");
+    let warnings = warnings
+        .iter()
+        .map(|warning| format!("\n    {}", json_string(warning)))
+        .collect::<Vec<_>>();
+    json.push_str(&warnings.join(","));
+    json.push_str(if warnings.is_empty() {
+        "]\n}\n"
+    } else {
+        "\n  ]\n}\n"
+    });
+    json
+}
+
+/// Writes the JSON summary of a run to `output`, `-` prints it to stdout instead
+pub(super) fn write(
+    output: &str,
+    version: &str,
+    packages: &[PackageReport],
+    warnings: &[String],
+) -> io::Result<Option<PathBuf>> {
+    let json = render(version, packages, warnings);
+    if output == "-" {
+        print!("{json}");
+        return Ok(None);
+    }
+
+    let path = PathBuf::from(output);
+    if let Some(parent) = path
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+    {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(&path, json)?;
+    Ok(Some(path))
+}
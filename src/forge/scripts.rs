@@ -0,0 +1,95 @@
+use std::{fs, io};
+
+use super::{FileType, Variables};
+
+/// Token a maintainer script can contain to choose where generated fragments are inserted
+const FRAGMENT_TOKEN: &str = "#DEBFORGE#";
+
+/// Ownership applied to an installed path by the generated postinst, from `--owner path:user:group`
+pub(crate) struct Owner {
+    pub(crate) path: String,
+    pub(crate) user: String,
+    pub(crate) group: String,
+}
+
+/// Quotes `value` for a POSIX shell
+fn quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', r"'\''"))
+}
+
+/// Inserts `fragment` into an existing maintainer script, replacing `#DEBFORGE#` when present, otherwise right after
+/// the shebang and any leading `set -e` so it runs before the script can `exit`
+pub(super) fn insert_fragment(script: &str, fragment: &str) -> String {
+    if script.contains(FRAGMENT_TOKEN) {
+        return script.replacen(FRAGMENT_TOKEN, fragment.trim_end(), 1);
+    }
+
+    let mut lines = script.lines().peekable();
+    let mut out = String::new();
+    while let Some(line) = lines
+        .next_if(|line| line.starts_with("#!") || line.trim() == "set -e" || line.trim().is_empty())
+    {
+        out.push_str(line);
+        out.push('\n');
+    }
+    if !out.is_empty() && !out.ends_with("\n\n") {
+        out.push('\n');
+    }
+    out.push_str(fragment);
+    out.push('\n');
+    for line in lines {
+        out.push_str(line);
+        out.push('\n');
+    }
+    out
+}
+
+/// A postinst running only the generated `fragment`
+pub(super) fn generated_postinst(fragment: &str) -> String {
+    format!("#!/bin/sh\nset -e\n\n{fragment}\nexit 0\n")
+}
+
+impl Variables {
+    /// postinst fragment applying every `--owner` once the package is configured, `None` without any owners
+    pub(super) fn owner_fragment(&self) -> Option<String> {
+        if self.owners.is_empty() {
+            return None;
+        }
+
+        let mut fragment =
+            String::from("# Added by debforge for --owner\nif [ \"$1\" = \"configure\" ]; then\n");
+        for owner in self.owners.iter() {
+            fragment.push_str(&format!(
+                "    chown {}:{} {}\n",
+                quote(&owner.user),
+                quote(&owner.group),
+                quote(&owner.path)
+            ));
+        }
+        fragment.push_str("fi\n");
+        Some(fragment)
+    }
+
+    /// Every generated postinst fragment joined together, `None` when nothing is generated
+    pub(super) fn postinst_fragment(&self) -> Option<String> {
+        let fragments = [self.owner_fragment(), self.user_install_fragment()]
+            .into_iter()
+            .flatten()
+            .collect::<Vec<_>>();
+        (!fragments.is_empty()).then(|| fragments.join("\n"))
+    }
+
+    /// Writes a postinst holding only the generated fragments when the project does not provide one
+    pub(super) fn write_generated_postinst(&self, fragment: &str) -> io::Result<()> {
+        fs::create_dir_all(self.get_file_type_path(FileType::PostInst))?;
+        let output = self.get_output_path(FileType::PostInst);
+        fs::write(&output, generated_postinst(fragment))?;
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(output, fs::Permissions::from_mode(0o755))?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,69 @@
+use std::{fs, io, path::Path, process::Command};
+
+use super::{deb::SCRIPTS, error::Error, tool};
+use crate::{fatal, warn};
+
+/// Runs `shellcheck` on each maintainer script staged under `DEBIAN`, reporting every finding as a warning or under
+/// `--strict` as an error. Returns `false` when shellcheck could not be run so the check can be skipped for the
+/// remaining packages
+pub(super) fn check(stage_root: &Path, strict: bool) -> Result<bool, Error> {
+    let control_dir = stage_root.join("DEBIAN");
+    let mut scripts = Vec::new();
+    for name in SCRIPTS {
+        let path = control_dir.join(name);
+        if !path.is_file() {
+            continue;
+        }
+        // Only shell scripts, a maintainer script may be written in any interpreted language
+        let shebang = fs::read_to_string(&path)?
+            .lines()
+            .next()
+            .unwrap_or_default()
+            .to_string();
+        if ["sh", "bash", "dash"]
+            .iter()
+            .any(|shell| shebang.starts_with("#!") && shebang.trim_end().ends_with(shell))
+        {
+            scripts.push(path)
+        }
+    }
+    if scripts.is_empty() {
+        return Ok(true);
+    }
+
+    let output = match tool::output(
+        Command::new("shellcheck")
+            .arg("--format=gcc")
+            .args(&scripts),
+    ) {
+        Ok(output) => output,
+        Err(err) if err.kind() == io::ErrorKind::TimedOut => return Err(err.into()),
+        Err(err) => {
+            warn!("failed to run shellcheck, skipping --shellcheck: {err}");
+            return Ok(false);
+        }
+    };
+
+    // Findings are listed on stdout as `file:line:column: level: message [SCxxxx]` with a non zero exit
+    let findings = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(String::from)
+        .collect::<Vec<_>>();
+    if findings.is_empty() && !output.status.success() {
+        return Err(fatal!(
+            "shellcheck failed:\n{}",
+            String::from_utf8_lossy(&output.stderr).trim_end()
+        ));
+    }
+    for finding in findings.iter() {
+        warn!("shellcheck: {finding}")
+    }
+    if strict && !findings.is_empty() {
+        return Err(fatal!(
+            "shellcheck reported {} finding(s) in: '{}'",
+            findings.len(),
+            control_dir.display()
+        ));
+    }
+    Ok(true)
+}
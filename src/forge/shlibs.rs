@@ -0,0 +1,74 @@
+use std::{fs, io};
+
+use super::{FileType, Variables, control::Control, elf};
+use crate::warn;
+
+/// Libraries every Debian system has installed, a heuristic rather than the output of `dpkg-shlibdeps`
+const BASE_LIBRARIES: [&str; 14] = [
+    "libc.so.6",
+    "libm.so.6",
+    "libdl.so.2",
+    "libpthread.so.0",
+    "librt.so.1",
+    "libutil.so.1",
+    "libresolv.so.2",
+    "libcrypt.so.1",
+    "libgcc_s.so.1",
+    "libstdc++.so.6",
+    "libz.so.1",
+    "ld-linux-x86-64.so.2",
+    "ld-linux-aarch64.so.1",
+    "linux-vdso.so.1",
+];
+
+/// The package name Debian conventionally gives a library, eg. `libssl.so.3` is shipped by `libssl3` and
+/// `libfoo2.so.1` by `libfoo2-1`
+fn package_for(soname: &str) -> Option<String> {
+    let (name, version) = soname.split_once(".so.")?;
+    let separator = if name.ends_with(|char: char| char.is_ascii_digit()) {
+        "-"
+    } else {
+        ""
+    };
+    Some(format!("{name}{separator}{version}").to_ascii_lowercase())
+}
+
+impl Variables {
+    /// Warns about libraries the staged binary links to that are neither part of a base system nor seemingly listed
+    /// in the staged control file's `Depends`
+    pub(super) fn check_linked_libraries(&self) -> io::Result<()> {
+        let binary = self.get_output_path(FileType::Binary);
+        if !elf::is_elf(&binary)? {
+            return Ok(());
+        }
+
+        let needed = elf::needed_libraries(&binary)?;
+        let control =
+            fs::read_to_string(self.get_output_path(FileType::Control)).unwrap_or_default();
+        let control = Control::parse(&control);
+        let depends = control
+            .get("Depends")
+            .unwrap_or_default()
+            .split([',', '|'])
+            .filter_map(|dependency| dependency.split_whitespace().next())
+            .collect::<Vec<_>>();
+
+        let missing = needed
+            .iter()
+            .filter(|library| !BASE_LIBRARIES.contains(&library.as_str()))
+            .filter(|library| {
+                package_for(library).is_none_or(|package| !depends.contains(&package.as_str()))
+            })
+            .map(String::as_str)
+            .collect::<Vec<_>>();
+
+        if !missing.is_empty() {
+            warn!(
+                "binary for {} links to libraries outside of a base system: {}, they may need to be added to Depends",
+                self.architecture.short(),
+                missing.join(", ")
+            )
+        }
+        Ok(())
+    }
+}
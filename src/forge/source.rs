@@ -0,0 +1,136 @@
+use std::{
+    fs,
+    io::{self, BufWriter, Write},
+    path::Path,
+};
+
+use flate2::{Compression, GzBuilder};
+use tar::{Builder, EntryType, Header};
+
+use super::{Variables, changelog, glob};
+
+/// Directories that never belong in an upstream source tarball
+const SKIPPED_DIRS: [&str; 3] = ["target", "build", ".git"];
+
+/// The upstream part of a Debian version, with any epoch and Debian revision removed, eg. `1.2.3` from `1:1.2.3-1`
+pub(super) fn upstream_version(version: &str) -> &str {
+    let version = version.split_once(':').map_or(version, |(_, rest)| rest);
+    version
+        .rsplit_once('-')
+        .map_or(version, |(upstream, _)| upstream)
+}
+
+struct TarOptions<'a> {
+    excludes: &'a [String],
+    /// Sorts entries and normalizes their metadata so identical sources produce identical tarballs
+    reproducible: bool,
+    mtime: u64,
+}
+
+impl Variables {
+    /// Writes `{source}_{upstream-version}.orig.tar.gz` of the project directory next to the staged packages
+    pub(super) fn write_orig_tarball(&self, excludes: &[String]) -> io::Result<()> {
+        let reproducible = self.reproducible;
+        let upstream = upstream_version(&self.version);
+        let dist_dir = self.get_dist_dir();
+        fs::create_dir_all(&dist_dir)?;
+
+        let tarball = dist_dir.join(format!("{}_{upstream}.orig.tar.gz", self.package_name));
+        let mtime = changelog::build_timestamp();
+
+        let mut gz = GzBuilder::new();
+        if reproducible {
+            gz = gz.mtime(mtime as u32);
+        }
+        let encoder = gz.write(
+            BufWriter::new(fs::File::create(&tarball)?),
+            Compression::default(),
+        );
+
+        let mut builder = Builder::new(encoder);
+        builder.follow_symlinks(false);
+
+        let options = TarOptions {
+            excludes,
+            reproducible,
+            mtime,
+        };
+        let prefix = format!("{}-{upstream}", self.package_name);
+        append_dir(&mut builder, &self.project_dir, "", &prefix, &options)?;
+
+        builder.into_inner()?.finish()?.flush()?;
+        println!("Created source tarball: '{}'", tarball.display());
+        Ok(())
+    }
+}
+
+fn header_for(metadata: &fs::Metadata, entry_type: EntryType, options: &TarOptions) -> Header {
+    let mut header = Header::new_gnu();
+    header.set_metadata(metadata);
+    header.set_entry_type(entry_type);
+
+    if options.reproducible {
+        header.set_mtime(options.mtime);
+        header.set_uid(0);
+        header.set_gid(0);
+        let _ = header.set_username("");
+        let _ = header.set_groupname("");
+    }
+    header
+}
+
+/// Appends the contents of `dir` to the tarball, `relative` is the `/` separated path of `dir` within the project
+fn append_dir<W: Write>(
+    builder: &mut Builder<W>,
+    dir: &Path,
+    relative: &str,
+    prefix: &str,
+    options: &TarOptions,
+) -> io::Result<()> {
+    let mut entries = fs::read_dir(dir)?.collect::<io::Result<Vec<_>>>()?;
+    if options.reproducible {
+        entries.sort_by_key(|entry| entry.file_name());
+    }
+
+    for entry in entries {
+        let file_name = entry.file_name();
+        let Some(name) = file_name.to_str() else {
+            crate::warn!(
+                "skipped non UTF-8 path in source tarball: '{}'",
+                entry.path().display()
+            );
+            continue;
+        };
+
+        let path = if relative.is_empty() {
+            name.to_string()
+        } else {
+            format!("{relative}/{name}")
+        };
+        let file_type = entry.file_type()?;
+
+        if relative.is_empty() && file_type.is_dir() && SKIPPED_DIRS.contains(&name) {
+            continue;
+        }
+        if glob::matches_any(options.excludes, &path) {
+            continue;
+        }
+
+        let metadata = fs::symlink_metadata(entry.path())?;
+        let archive_path = format!("{prefix}/{path}");
+
+        if file_type.is_symlink() {
+            let mut header = header_for(&metadata, EntryType::Symlink, options);
+            builder.append_link(&mut header, &archive_path, fs::read_link(entry.path())?)?;
+        } else if file_type.is_dir() {
+            let mut header = header_for(&metadata, EntryType::Directory, options);
+            header.set_size(0);
+            builder.append_data(&mut header, &archive_path, io::empty())?;
+            append_dir(builder, &entry.path(), &path, prefix, options)?;
+        } else if file_type.is_file() {
+            let mut header = header_for(&metadata, EntryType::Regular, options);
+            builder.append_data(&mut header, &archive_path, fs::File::open(entry.path())?)?;
+        }
+    }
+    Ok(())
+}
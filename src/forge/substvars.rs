@@ -0,0 +1,100 @@
+use std::{fs, io, path::Path};
+
+use super::{Variables, control::Control};
+
+const PREFIX: &str = "${";
+
+/// Control fields holding comma separated lists, a substitution that expands to nothing leaves an empty item behind
+const LIST_FIELDS: [&str; 10] = [
+    "Depends",
+    "Pre-Depends",
+    "Recommends",
+    "Suggests",
+    "Enhances",
+    "Breaks",
+    "Conflicts",
+    "Replaces",
+    "Provides",
+    "Built-Using",
+];
+
+/// Parses a dpkg-gencontrol style substvars file, `name=value` per line where `#` starts a comment. The `?=` form
+/// of an optional variable is read the same way
+fn parse(contents: &str) -> Vec<(String, String)> {
+    contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| line.split_once('='))
+        .map(|(name, value)| {
+            let name = name.strip_suffix('?').unwrap_or(name);
+            (name.trim().to_string(), value.trim().to_string())
+        })
+        .collect()
+}
+
+/// Replaces each `${name}` in `line` with its value from `substvars`. `${env:VAR}` references are left for the
+/// environment expansion. Undefined names expand to nothing, as with dpkg-gencontrol, and are passed to `undefined`
+pub(super) fn expand(
+    line: &str,
+    substvars: &[(String, String)],
+    mut undefined: impl FnMut(&str),
+) -> String {
+    let mut out = String::with_capacity(line.len());
+    let mut rest = line;
+
+    while let Some(start) = rest.find(PREFIX) {
+        out.push_str(&rest[..start]);
+        let reference = &rest[start..];
+
+        let Some(end) = reference.find('}') else {
+            rest = reference;
+            break;
+        };
+        let name = &reference[PREFIX.len()..end];
+
+        if name.starts_with("env:") {
+            out.push_str(&reference[..=end]);
+        } else {
+            match substvars.iter().find(|(key, _)| key == name) {
+                Some((_, value)) => out.push_str(value),
+                None => undefined(name),
+            }
+        }
+        rest = &reference[end + 1..];
+    }
+
+    out.push_str(rest);
+    out
+}
+
+/// Drops the empty items a substitution left in list fields, and any list field left without an item
+pub(super) fn tidy(control: &mut Control) {
+    for field in LIST_FIELDS {
+        let Some(value) = control.get(field) else {
+            continue;
+        };
+        let items = value
+            .split(',')
+            .map(str::trim)
+            .filter(|item| !item.is_empty())
+            .collect::<Vec<_>>();
+        if items.is_empty() {
+            control.remove(field);
+        } else {
+            let tidied = items.join(", ");
+            control.set(field, &tidied);
+        }
+    }
+}
+
+impl Variables {
+    /// Reads the package's `substvars` file, if any, for `${name}` substitution in its control file
+    pub(super) fn load_substvars(&mut self, path: Option<&Path>) -> io::Result<()> {
+        self.substvars = match path {
+            Some(path) => parse(&fs::read_to_string(path)?),
+            None => Vec::new(),
+        };
+        Ok(())
+    }
+}
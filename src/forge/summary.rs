@@ -0,0 +1,115 @@
+use std::{
+    collections::BTreeMap,
+    fs, io,
+    path::{Component, Path},
+};
+
+use super::error::Error;
+use crate::fatal;
+
+const UNITS: [&str; 4] = ["B", "KiB", "MiB", "GiB"];
+
+/// Number of files and total bytes staged into each install directory of a single package tree
+#[derive(Default)]
+pub(super) struct Summary {
+    dirs: BTreeMap<String, (usize, u64)>,
+    /// GNU build-id of the staged binary, to correlate a shipped package with the build it came from
+    pub(super) build_id: Option<String>,
+}
+
+impl Summary {
+    /// Adds a staged file to the install directory it landed in, eg. `usr/share/icons` or `DEBIAN`
+    pub(super) fn record(&mut self, stage_root: &Path, output: &Path) -> io::Result<()> {
+        let size = fs::symlink_metadata(output)?.len();
+        let relative = output
+            .strip_prefix(stage_root)
+            .expect("staged files are always within the stage root");
+
+        let mut components = relative
+            .parent()
+            .into_iter()
+            .flat_map(Path::components)
+            .filter_map(|component| match component {
+                Component::Normal(name) => name.to_str(),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        components.truncate(if components.first() == Some(&"DEBIAN") {
+            1
+        } else {
+            3
+        });
+
+        let entry = self.dirs.entry(components.join("/")).or_default();
+        entry.0 += 1;
+        entry.1 += size;
+        Ok(())
+    }
+
+    pub(super) fn file_count(&self) -> usize {
+        self.dirs.values().map(|&(count, _)| count).sum()
+    }
+
+    pub(super) fn print(&self, stage_root: &Path) {
+        println!("Staged: '{}'", stage_root.display());
+        if let Some(build_id) = self.build_id.as_deref() {
+            println!("    build-id: {build_id}")
+        }
+
+        let width = self.dirs.keys().map(String::len).max().unwrap_or_default();
+        for (dir, &(count, bytes)) in self.dirs.iter() {
+            println!(
+                "    {dir:<width$}  {count:>3} file{}  {:>10}",
+                if count == 1 { " " } else { "s" },
+                human_bytes(bytes)
+            );
+        }
+    }
+}
+
+/// Total bytes of the files a staged package installs, everything but its `DEBIAN` directory
+pub(super) fn installed_bytes(stage_root: &Path) -> io::Result<u64> {
+    fn walk(dir: &Path, skip: Option<&Path>) -> io::Result<u64> {
+        let mut total = 0;
+        for entry in fs::read_dir(dir)? {
+            let entry = entry?;
+            let path = entry.path();
+            if Some(path.as_path()) == skip {
+                continue;
+            }
+            total += match entry.file_type()?.is_dir() {
+                true => walk(&path, None)?,
+                false => fs::symlink_metadata(&path)?.len(),
+            };
+        }
+        Ok(total)
+    }
+    walk(stage_root, Some(&stage_root.join("DEBIAN")))
+}
+
+/// Errors when `bytes` of `what`, eg. a package or a `.deb`, exceed the `--max-size` `budget`
+pub(super) fn check_budget(what: &str, bytes: u64, budget: u64) -> Result<(), Error> {
+    if bytes > budget {
+        return Err(fatal!(
+            "{what} is {} ({bytes} bytes), over the --max-size budget of {} ({budget} bytes)",
+            human_bytes(bytes),
+            human_bytes(budget)
+        ));
+    }
+    Ok(())
+}
+
+pub(super) fn human_bytes(bytes: u64) -> String {
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+
+    if unit == 0 {
+        format!("{bytes} {}", UNITS[0])
+    } else {
+        format!("{size:.1} {}", UNITS[unit])
+    }
+}
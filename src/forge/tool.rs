@@ -0,0 +1,94 @@
+use std::{
+    io::{self, Read},
+    process::{Child, Command, Output, Stdio},
+    sync::OnceLock,
+    thread,
+    time::{Duration, Instant},
+};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+/// Limit on how long any spawned external tool may run, set once from `--tool-timeout`
+pub(super) static TOOL_TIMEOUT: OnceLock<Duration> = OnceLock::new();
+
+fn read_to_end(mut pipe: impl Read + Send + 'static) -> thread::JoinHandle<Vec<u8>> {
+    thread::spawn(move || {
+        let mut buf = Vec::new();
+        let _ = pipe.read_to_end(&mut buf);
+        buf
+    })
+}
+
+fn kill(mut child: Child) -> io::Result<()> {
+    child.kill()?;
+    child.wait().map(drop)
+}
+
+/// Whether `program` is an executable file in one of the directories of `PATH`
+pub(super) fn on_path(program: &str) -> bool {
+    std::env::var_os("PATH")
+        .is_some_and(|paths| std::env::split_paths(&paths).any(|dir| dir.join(program).is_file()))
+}
+
+/// `command` as it would be typed into a shell, arguments are quoted where needed so it can be copied as is
+pub(super) fn display(command: &Command) -> String {
+    std::iter::once(command.get_program())
+        .chain(command.get_args())
+        .map(|arg| {
+            let arg = arg.to_string_lossy();
+            let safe = !arg.is_empty()
+                && arg
+                    .chars()
+                    .all(|char| char.is_ascii_alphanumeric() || "-_./:=,+@%".contains(char));
+            if safe {
+                arg.into_owned()
+            } else {
+                format!("'{}'", arg.replace('\'', r"'\''"))
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Runs `command` to completion like `Command::output`, killing it once it exceeds `--tool-timeout`. A timed out
+/// command returns an error of kind `io::ErrorKind::TimedOut`
+pub(super) fn output(command: &mut Command) -> io::Result<Output> {
+    let Some(&timeout) = TOOL_TIMEOUT.get() else {
+        return command.output();
+    };
+
+    let mut child = command
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+
+    // Drained on their own threads so a chatty tool can not block on a full pipe while we wait on it
+    let stdout = read_to_end(child.stdout.take().expect("stdout is piped"));
+    let stderr = read_to_end(child.stderr.take().expect("stderr is piped"));
+
+    let start = Instant::now();
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+        if start.elapsed() >= timeout {
+            kill(child)?;
+            return Err(io::Error::new(
+                io::ErrorKind::TimedOut,
+                format!(
+                    "'{}' was killed after exceeding --tool-timeout of {}s",
+                    command.get_program().to_string_lossy(),
+                    timeout.as_secs()
+                ),
+            ));
+        }
+        thread::sleep(POLL_INTERVAL);
+    };
+
+    Ok(Output {
+        status,
+        stdout: stdout.join().unwrap_or_default(),
+        stderr: stderr.join().unwrap_or_default(),
+    })
+}
@@ -0,0 +1,47 @@
+/// Directives dpkg accepts in a `DEBIAN/triggers` file, see deb-triggers(5)
+const DIRECTIVES: [&str; 6] = [
+    "interest",
+    "interest-await",
+    "interest-noawait",
+    "activate",
+    "activate-await",
+    "activate-noawait",
+];
+
+/// Finds lines of a `DEBIAN/triggers` file that dpkg would reject or that name an implausible trigger
+pub(super) fn validate(contents: &str) -> Vec<String> {
+    let mut problems = Vec::new();
+
+    for line in contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+    {
+        let mut tokens = line.split_whitespace();
+        let directive = tokens.next().expect("blank lines are filtered");
+
+        if !DIRECTIVES.contains(&directive) {
+            problems.push(format!("unknown trigger directive: '{directive}'"));
+            continue;
+        }
+
+        match (tokens.next(), tokens.next()) {
+            (None, _) => problems.push(format!("'{directive}' is missing a trigger name")),
+            (Some(_), Some(extra)) => problems.push(format!(
+                "'{directive}' takes 1 trigger name, found extra: '{extra}'"
+            )),
+            // File triggers are absolute paths, explicit triggers are plain names
+            (Some(name), None)
+                if name.starts_with('/') && (name.contains("//") || name.len() == 1) =>
+            {
+                problems.push(format!("implausible file trigger path: '{name}'"))
+            }
+            (Some(name), None) if !name.starts_with('/') && name.contains('/') => problems.push(
+                format!("trigger name: '{name}' must be an absolute path or contain no '/'"),
+            ),
+            _ => (),
+        }
+    }
+
+    problems
+}
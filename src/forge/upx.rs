@@ -0,0 +1,42 @@
+use std::{fs, io, path::Path, process::Command};
+
+use super::{error::Error, summary::human_bytes, tool};
+use crate::{fatal, warn};
+
+pub(super) fn command(binary: &Path) -> Command {
+    let mut command = Command::new("upx");
+    command.arg("--best").arg("-q").arg(binary);
+    command
+}
+
+/// Compresses the staged binary in place with `upx`, the project's own binary is never touched. Returns `false` when
+/// upx could not be run so compression can be skipped for the remaining targets
+pub(super) fn compress(binary: &Path, quiet: bool) -> Result<bool, Error> {
+    let before = fs::metadata(binary)?.len();
+
+    let output = match tool::output(&mut command(binary)) {
+        Ok(output) => output,
+        Err(err) if err.kind() == io::ErrorKind::TimedOut => return Err(err.into()),
+        Err(err) => {
+            warn!("failed to run upx, skipping --compress-binary: {err}");
+            return Ok(false);
+        }
+    };
+    if !output.status.success() {
+        return Err(fatal!(
+            "upx failed to compress: '{}'\n{}",
+            binary.display(),
+            String::from_utf8_lossy(&output.stderr).trim_end()
+        ));
+    }
+
+    if !quiet {
+        let after = fs::metadata(binary)?.len();
+        println!(
+            "Compressed binary: {} -> {}",
+            human_bytes(before),
+            human_bytes(after)
+        )
+    }
+    Ok(true)
+}
@@ -0,0 +1,70 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use super::Variables;
+
+/// Directory under `debian/` holding files laid out as they should appear in a user's home directory
+pub(super) const TEMPLATE_DIR: &str = "user";
+
+impl Variables {
+    /// Install directory of the home directory templates, eg. `usr/share/my-app/user`
+    fn user_template_dir(&self) -> String {
+        format!("usr/share/{}/{TEMPLATE_DIR}", self.package_name)
+    }
+
+    /// Copies every file within `dir` into the package's template directory, returning the staged paths
+    pub(super) fn stage_user_templates(&self, dir: &Path) -> io::Result<Vec<PathBuf>> {
+        let mut staged = Vec::new();
+        copy_templates(
+            dir,
+            &self.get_stage_root().join(self.user_template_dir()),
+            &mut staged,
+        )?;
+        Ok(staged)
+    }
+
+    /// postinst fragment copying the staged templates into the home directory of the user who ran the install through
+    /// `sudo`, `None` without `--user-install`. Files already present are left untouched
+    pub(super) fn user_install_fragment(&self) -> Option<String> {
+        if !self.user_install {
+            return None;
+        }
+
+        Some(format!(
+            r#"# Added by debforge for --user-install
+if [ "$1" = "configure" ] && [ -n "$SUDO_USER" ] && [ "$SUDO_USER" != "root" ]; then
+    user_home=$(getent passwd "$SUDO_USER" | cut -d: -f6)
+    if [ -d "$user_home" ]; then
+        (cd /{} && find . -type f) | while IFS= read -r template; do
+            target="$user_home/${{template#./}}"
+            [ -e "$target" ] && continue
+            runuser -u "$SUDO_USER" -- mkdir -p "$(dirname "$target")" \
+                && runuser -u "$SUDO_USER" -- cp "/{}/${{template#./}}" "$target" \
+                || echo "{}: could not copy $target" >&2
+        done
+    fi
+fi
+"#,
+            self.user_template_dir(),
+            self.user_template_dir(),
+            self.package_name
+        ))
+    }
+}
+
+fn copy_templates(dir: &Path, output: &Path, staged: &mut Vec<PathBuf>) -> io::Result<()> {
+    fs::create_dir_all(output)?;
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let target = output.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            copy_templates(&entry.path(), &target, staged)?;
+            continue;
+        }
+        fs::copy(entry.path(), &target)?;
+        staged.push(target);
+    }
+    Ok(())
+}
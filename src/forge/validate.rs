@@ -0,0 +1,414 @@
+use std::path::PathBuf;
+
+use super::{
+    DebBuilder, FileType, PKG_NAME, Package, REQUIRED_DEB_FILES, TOML, Variables, changelog, color,
+    control::Control, error::Error, icon, tool, upx, warnings,
+};
+use crate::{args::Architecture, fatal, warn};
+
+/// Fields dpkg-deb refuses to build a binary package without
+const REQUIRED_CONTROL_FIELDS: [&str; 5] = [
+    "Package",
+    "Version",
+    "Architecture",
+    "Maintainer",
+    "Description",
+];
+
+/// Checks a version against deb-version(7): `[epoch:]upstream[-revision]`
+pub(super) fn version(version: &str) -> Option<String> {
+    let (epoch, rest) = match version.split_once(':') {
+        Some((epoch, rest)) => (Some(epoch), rest),
+        None => (None, version),
+    };
+    if let Some(epoch) = epoch
+        && (epoch.is_empty() || !epoch.bytes().all(|b| b.is_ascii_digit()))
+    {
+        return Some(format!("epoch of version: '{version}' must be a number"));
+    }
+
+    let (upstream, revision) = match rest.rsplit_once('-') {
+        Some((upstream, revision)) => (upstream, Some(revision)),
+        None => (rest, None),
+    };
+    if !upstream.starts_with(|c: char| c.is_ascii_digit()) {
+        return Some(format!(
+            "upstream version of: '{version}' must start with a digit"
+        ));
+    }
+    if let Some(c) = upstream.chars().find(|&c| {
+        !(c.is_ascii_alphanumeric()
+            || matches!(c, '.' | '+' | '~')
+            || c == '-' && revision.is_some()
+            || c == ':' && epoch.is_some())
+    }) {
+        return Some(format!(
+            "upstream version of: '{version}' contains invalid character: '{c}'"
+        ));
+    }
+
+    if let Some(revision) = revision
+        && (revision.is_empty()
+            || !revision
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '+' | '~')))
+    {
+        return Some(format!("debian revision of: '{version}' is invalid"));
+    }
+    None
+}
+
+/// Checks a package name against Debian policy 5.6.1, lowercase alphanumerics, `+`, `-` and `.`
+fn package_name(name: &str) -> Option<String> {
+    let valid = name.len() >= 2
+        && name.starts_with(|c: char| c.is_ascii_lowercase() || c.is_ascii_digit())
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || matches!(c, '+' | '-' | '.'));
+    (!valid).then(|| format!("invalid package name: '{name}'"))
+}
+
+/// Problems with a rendered binary control file
+pub(super) fn control(control: &Control) -> Vec<String> {
+    let mut problems = REQUIRED_CONTROL_FIELDS
+        .into_iter()
+        .filter(|field| control.get(field).is_none_or(str::is_empty))
+        .map(|field| format!("missing required field: '{field}'"))
+        .collect::<Vec<_>>();
+
+    problems.extend(control.get("Package").and_then(package_name));
+    problems.extend(control.get("Version").and_then(version));
+    problems
+}
+
+/// Problems with a rendered desktop entry, see the freedesktop desktop entry specification
+pub(super) fn desktop(contents: &str) -> Vec<String> {
+    let groups = desktop_groups(contents);
+    let Some((_, keys)) = groups
+        .first()
+        .filter(|(group, _)| *group == "Desktop Entry")
+    else {
+        return vec!["first group must be '[Desktop Entry]'".to_string()];
+    };
+
+    let mut problems = Vec::new();
+    for key in ["Type", "Name"] {
+        if desktop_key(keys, key).is_none() {
+            problems.push(format!("missing required key: '{key}'"));
+        }
+    }
+    if desktop_key(keys, "Type") == Some("Application") && desktop_key(keys, "Exec").is_none() {
+        problems.push("'Application' entries require an 'Exec' key".to_string());
+    }
+
+    let actions = desktop_key(keys, "Actions")
+        .unwrap_or_default()
+        .split(';')
+        .map(str::trim)
+        .filter(|action| !action.is_empty())
+        .collect::<Vec<_>>();
+    let action_groups = groups
+        .iter()
+        .filter_map(|(group, keys)| Some((group.strip_prefix("Desktop Action ")?, keys)))
+        .collect::<Vec<_>>();
+
+    for action in actions.iter() {
+        match action_groups.iter().find(|(name, _)| name == action) {
+            Some((_, keys)) => {
+                for key in ["Name", "Exec"] {
+                    if desktop_key(keys, key).is_none() {
+                        problems.push(format!("action: '{action}' is missing required key: '{key}'"));
+                    }
+                }
+            }
+            None => problems.push(format!(
+                "action: '{action}' is listed in 'Actions' but has no '[Desktop Action {action}]' group"
+            )),
+        }
+    }
+    for (name, _) in action_groups {
+        if !actions.contains(&name) {
+            problems.push(format!(
+                "orphaned group: '[Desktop Action {name}]' is not listed in 'Actions'"
+            ));
+        }
+    }
+    problems
+}
+
+fn desktop_key<'a>(keys: &[(&str, &'a str)], key: &str) -> Option<&'a str> {
+    keys.iter().find(|&&(k, _)| k == key).map(|&(_, v)| v)
+}
+
+/// Splits a desktop entry into its `[Group]`s and their keys, ignoring blank lines and comments
+fn desktop_groups(contents: &str) -> Vec<(&str, Vec<(&str, &str)>)> {
+    let mut groups: Vec<(&str, Vec<(&str, &str)>)> = Vec::new();
+
+    for line in contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+    {
+        if let Some(group) = line
+            .strip_prefix('[')
+            .and_then(|line| line.strip_suffix(']'))
+        {
+            groups.push((group, Vec::new()));
+        } else if let Some((_, keys)) = groups.last_mut()
+            && let Some((key, value)) = line.split_once('=')
+        {
+            keys.push((key.trim(), value.trim()));
+        } else if groups.is_empty() {
+            groups.push(("", Vec::new()));
+        }
+    }
+    groups
+}
+
+impl Variables {
+    /// Problems with the discovered files of every package as they would be staged, shared by `--dry-run` and
+    /// `--check`. Nothing is written
+    pub(super) fn validate_packages(&mut self, packages: &[Package]) -> Result<Vec<String>, Error> {
+        let mut problems = Vec::new();
+        problems.extend(version(&self.version));
+
+        for (i, package) in packages.iter().enumerate() {
+            self.select_package(package, i == 0);
+            self.load_control_fields(&package.files)?;
+            self.load_substvars(
+                package
+                    .files
+                    .files
+                    .get(&FileType::Substvars)
+                    .map(PathBuf::as_path),
+            )?;
+            let mut control_package = None;
+            let mut changelog = (i == 0).then(|| self.changelog_entry.clone()).flatten();
+
+            for (file_type, path) in package.files.iter_all() {
+                if file_type.is_icon() {
+                    problems.extend(
+                        icon::validate(file_type, path)
+                            .into_iter()
+                            .map(|problem| format!("{problem} in: '{}'", path.display())),
+                    );
+                }
+                if !self.is_rendered(file_type, path) {
+                    continue;
+                }
+                let contents = self.render_text(file_type, path)?;
+                let found = match file_type {
+                    FileType::Control => {
+                        let parsed = Control::parse(&contents);
+                        control_package = parsed.get("Package").map(String::from);
+                        control(&parsed)
+                    }
+                    FileType::Desktop => desktop(&contents),
+                    FileType::Changelog => {
+                        changelog = Some(contents);
+                        Vec::new()
+                    }
+                    _ => Vec::new(),
+                };
+                problems.extend(
+                    found
+                        .into_iter()
+                        .map(|problem| format!("{problem} in: '{}'", path.display())),
+                );
+            }
+
+            if let (Some(control_package), Some(changelog)) = (control_package, changelog)
+                && package.doc_link.is_none()
+                && let Some(changelog_package) = changelog::top_package(&changelog)
+                && changelog_package != control_package
+            {
+                problems.push(format!(
+                    "control Package: '{control_package}' does not match changelog package: '{changelog_package}'"
+                ))
+            }
+        }
+        Ok(problems)
+    }
+}
+
+/// Command line input that decides which files a package must provide
+pub(super) struct Requirements<'a> {
+    /// Files the primary package must provide in addition to `REQUIRED_DEB_FILES`, from `--require`
+    pub(super) require: &'a [FileType],
+    pub(super) allow_missing: &'a [FileType],
+    pub(super) only: &'a [FileType],
+    pub(super) skip: &'a [FileType],
+    pub(super) generate_copyright: bool,
+}
+
+impl Variables {
+    /// Errors when a package is missing a required file that `--allow-missing` does not skip, then decides whether
+    /// the command at `$ExecPath` is staged
+    pub(super) fn check_required(
+        &mut self,
+        packages: &[Package],
+        requirements: Requirements,
+    ) -> Result<(), Error> {
+        let Requirements {
+            require,
+            allow_missing,
+            only,
+            skip,
+            generate_copyright,
+        } = requirements;
+
+        let mut skipped = Vec::new();
+        for (i, package) in packages.iter().enumerate() {
+            // `--require` and `--package-type` describe the primary package, secondary packages, eg. `-doc`, are exempt
+            let extra = if i == 0 { require } else { &[] };
+            for &required in REQUIRED_DEB_FILES.iter().chain(extra) {
+                if !only.is_empty() && !only.contains(&required) {
+                    continue;
+                }
+                let is_doc = matches!(required, FileType::Changelog | FileType::Copyright);
+                if is_doc && package.doc_link.is_some()
+                    || i == 0
+                        && match required {
+                            FileType::Changelog => self.changelog_entry.is_some(),
+                            FileType::Copyright => self.generated_copyright.is_some(),
+                            _ => false,
+                        }
+                {
+                    continue;
+                }
+                for &architecture in self.config.targets.iter() {
+                    if !package.files.contains_for(required, architecture)
+                        || skip.contains(&required)
+                    {
+                        let mut context = String::new();
+                        if packages.len() > 1 {
+                            context.push_str(&format!(" for package: '{}'", package.name));
+                        }
+                        if self.config.targets.len() > 1 {
+                            context.push_str(&format!(" on {}", architecture.short()));
+                        }
+                        if allow_missing.contains(&required) {
+                            skipped.push(format!("{required:?}{context}"));
+                            continue;
+                        }
+                        if required == FileType::Copyright && !generate_copyright {
+                            context.push_str(&format!(
+                                ", one can be generated from {TOML} with --generate-copyright"
+                            ));
+                        }
+                        return Err(fatal!(
+                            kind: "MissingRequiredFile",
+                            file: format!("{required:?}").to_lowercase();
+                            "could not locate a {required:?} file{context}"
+                        ));
+                    }
+                }
+            }
+
+            if package.files.contains(FileType::Desktop)
+                && !package
+                    .files
+                    .iter_all()
+                    .any(|(file_type, _)| file_type.is_icon())
+            {
+                warn!(
+                    kind: "MissingIcons";
+                    "found a Desktop file but no icons for package: '{}'",
+                    package.name
+                )
+            }
+        }
+
+        if !self.wrapper && packages[0].files.contains(FileType::Wrapper) {
+            warn!("found a Wrapper file, it is only staged with --wrapper")
+        }
+        let stages = |file_type| {
+            (only.is_empty() || only.contains(&file_type)) && !skip.contains(&file_type)
+        };
+        self.exec_staged = match self.wrapper {
+            true => stages(FileType::Wrapper) && packages[0].files.contains(FileType::Wrapper),
+            false => stages(FileType::Binary),
+        };
+
+        if !only.is_empty() {
+            warn!(
+                "--only stages just: {}, the staged package is incomplete",
+                only.iter()
+                    .map(|file_type| format!("{file_type:?}"))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )
+        }
+
+        if !skipped.is_empty() {
+            warn!(
+                "the staged package will NOT be policy compliant, skipped required files:\n    {}",
+                skipped.join("\n    ")
+            )
+        }
+        Ok(())
+    }
+
+    /// Warns about each of `problems` and prints the external tools staging would run, for `--dry-run`
+    pub(super) fn report_dry_run(
+        &mut self,
+        problems: &[String],
+        packages: &[Package],
+        binaries: &[(Architecture, PathBuf)],
+        compress_binary: bool,
+        build: Option<DebBuilder>,
+    ) -> Result<(), Error> {
+        for problem in problems.iter() {
+            warn!("{problem}")
+        }
+
+        // External tools that would modify the staged output are only printed
+        let mut commands = Vec::new();
+        for &(architecture, _) in binaries.iter() {
+            self.architecture = architecture;
+            for (i, package) in packages.iter().enumerate() {
+                self.select_package(package, i == 0);
+                if i == 0 && compress_binary {
+                    commands.push(upx::command(&self.get_output_path(FileType::Binary)))
+                }
+                if let Some(DebBuilder::DpkgDeb(compression, fakeroot)) = build {
+                    let (_, control) = self.read_control(&package.files)?;
+                    commands.push(self.dpkg_deb_command(
+                        compression,
+                        fakeroot,
+                        &self.deb_path(&control)?,
+                    ))
+                }
+            }
+        }
+        if !commands.is_empty() {
+            println!("Would run:");
+            for command in commands.iter() {
+                println!("    {}", tool::display(command))
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Reports the outcome of `--check`, returning the exit code it ends with
+pub(super) fn report_check(problems: &[String]) -> i32 {
+    let warning_count = warnings::count();
+    if problems.is_empty() && warning_count == 0 {
+        println!(
+            "{PKG_NAME}: {}",
+            color::stdout(color::GREEN, "Check passed")
+        );
+        return 0;
+    }
+
+    warnings::render();
+    eprintln!("{PKG_NAME}: {}", color::stderr(color::RED, "Check failed"));
+    for problem in problems.iter() {
+        eprintln!("    {problem}")
+    }
+    if warning_count > 0 {
+        eprintln!("    {warning_count} warning(s) reported above")
+    }
+    1
+}
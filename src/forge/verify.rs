@@ -0,0 +1,87 @@
+use std::{path::Path, process::Command};
+
+use super::{control::Control, error::Error, ownership, tool};
+use crate::fatal;
+
+/// Control fields a built package must carry over from the staged control file
+const VERIFIED_FIELDS: [&str; 3] = ["Package", "Version", "Architecture"];
+
+/// Output of `dpkg-deb <action> <deb>`
+fn dpkg_deb(action: &str, deb: &Path) -> Result<String, Error> {
+    let output = tool::output(Command::new("dpkg-deb").arg(action).arg(deb))
+        .map_err(|err| fatal!("failed to run dpkg-deb {action}: {err}"))?;
+    if !output.status.success() {
+        return Err(fatal!(
+            "dpkg-deb {action} failed on: '{}'\n{}",
+            deb.display(),
+            String::from_utf8_lossy(&output.stderr).trim_end()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Install paths listed by `dpkg-deb --contents`, eg. `usr/share/doc/my-app/copyright`. Each path follows the mode,
+/// owner, size, date and time columns, with or without a leading `./`. A link is listed with its target, which is
+/// dropped
+fn content_paths(contents: &str) -> Vec<&str> {
+    contents
+        .lines()
+        .map(|line| {
+            let mut rest = line;
+            for _ in 0..5 {
+                let column = rest.trim_start();
+                rest = column
+                    .find(char::is_whitespace)
+                    .map_or("", |end| &column[end..]);
+            }
+            rest.trim_start()
+        })
+        .map(|path| path.split_once(" -> ").map_or(path, |(path, _)| path))
+        .map(|path| path.trim_start_matches("./").trim_end_matches('/'))
+        .collect()
+}
+
+/// Inspects a built `.deb` with `dpkg-deb --info` and `--contents`, printing both unless `quiet`. Errors when a file
+/// staged in `stage_root` is missing from the archive or the archive's control fields differ from `control`
+pub(super) fn verify(
+    deb: &Path,
+    stage_root: &Path,
+    control: &Control,
+    quiet: bool,
+) -> Result<(), Error> {
+    let info = dpkg_deb("--info", deb)?;
+    let contents = dpkg_deb("--contents", deb)?;
+    if !quiet {
+        println!("Verified package: '{}'\n{info}{contents}", deb.display());
+    }
+
+    for field in VERIFIED_FIELDS {
+        let Some(expected) = control.get(field) else {
+            continue;
+        };
+        let expected_line = format!("{field}: {expected}");
+        if !info.lines().any(|line| line.trim() == expected_line) {
+            return Err(fatal!(
+                "built package: '{}' does not declare '{expected_line}'",
+                deb.display()
+            ));
+        }
+    }
+
+    let listed = content_paths(&contents);
+    let mut staged = Vec::new();
+    ownership::install_paths(stage_root, stage_root, &mut staged)?;
+    let missing = staged
+        .iter()
+        .filter(|path| !listed.contains(&path.trim_start_matches('/')))
+        .map(String::as_str)
+        .collect::<Vec<_>>();
+    if !missing.is_empty() {
+        return Err(fatal!(
+            "built package: '{}' is missing staged file(s):\n    {}",
+            deb.display(),
+            missing.join("\n    ")
+        ));
+    }
+    Ok(())
+}
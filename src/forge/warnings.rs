@@ -0,0 +1,94 @@
+use std::{fmt::Write as _, sync::Mutex};
+
+use super::{
+    PKG_NAME, color,
+    error::{self, ErrorFormat, json_string},
+};
+
+/// A non fatal problem, held until the collected warnings are rendered
+pub(crate) struct Warning {
+    /// Classifies the warning for `--error-format json`, eg. `UnresolvedPlaceholder`
+    pub(crate) kind: &'static str,
+    pub(crate) message: String,
+    /// Path of the file the warning concerns
+    pub(crate) file: Option<String>,
+}
+
+struct Collected {
+    warnings: Vec<Warning>,
+    /// Number of warnings already rendered
+    rendered: usize,
+}
+
+/// Every warning reported by the run
+static COLLECTED: Mutex<Collected> = Mutex::new(Collected {
+    warnings: Vec::new(),
+    rendered: 0,
+});
+
+pub(crate) fn push(warning: Warning) {
+    if let Ok(mut collected) = COLLECTED.lock() {
+        collected.warnings.push(warning)
+    }
+}
+
+/// Number of warnings reported so far, rendered or not
+pub(crate) fn count() -> usize {
+    COLLECTED
+        .lock()
+        .map(|collected| collected.warnings.len())
+        .unwrap_or_default()
+}
+
+/// The message of every warning reported so far, for `--summary-json`
+pub(super) fn messages() -> Vec<String> {
+    COLLECTED
+        .lock()
+        .map(|collected| {
+            collected
+                .warnings
+                .iter()
+                .map(|warning| warning.message.clone())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn render_one(warning: &Warning, format: ErrorFormat) {
+    match format {
+        ErrorFormat::Text => eprintln!(
+            "{PKG_NAME}: {} {}",
+            color::stderr(color::YELLOW, "Warning"),
+            warning.message
+        ),
+        ErrorFormat::Json => {
+            let mut json = format!(
+                "{{ \"warning\": {}, \"kind\": {}",
+                json_string(&warning.message),
+                json_string(warning.kind)
+            );
+            if let Some(file) = warning.file.as_deref() {
+                let _ = write!(json, ", \"file\": {}", json_string(file));
+            }
+            eprintln!("{json} }}")
+        }
+    }
+}
+
+/// Prints every warning not yet rendered, in the format chosen with `--error-format`
+pub(crate) fn render() {
+    let Ok(mut collected) = COLLECTED.lock() else {
+        return;
+    };
+    let format = error::format();
+    for warning in collected.warnings[collected.rendered..].iter() {
+        render_one(warning, format)
+    }
+    collected.rendered = collected.warnings.len();
+}
+
+/// Renders the collected warnings and exits with `code`
+pub(crate) fn exit(code: i32) -> ! {
+    render();
+    std::process::exit(code)
+}
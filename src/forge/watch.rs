@@ -0,0 +1,35 @@
+/// Finds obvious problems in a `debian/watch` file that would make uscan silently fail
+pub(super) fn validate(contents: &str) -> Vec<String> {
+    let mut lines = contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'));
+
+    let mut problems = Vec::new();
+
+    match lines.next() {
+        Some(header) if is_version_header(header) => (),
+        Some(header) => problems.push(format!(
+            "watch file must start with a 'version=' header, found: '{header}'"
+        )),
+        None => {
+            problems.push(String::from("watch file is empty"));
+            return problems;
+        }
+    }
+
+    if !lines.any(|line| line.contains("://")) {
+        problems.push(String::from("watch file contains no URL pattern lines"));
+    }
+
+    problems
+}
+
+fn is_version_header(line: &str) -> bool {
+    let Some((key, value)) = line.split_once(['=', ':']) else {
+        return false;
+    };
+
+    key.trim().eq_ignore_ascii_case("version")
+        && value.trim().parse::<u8>().is_ok_and(|version| version > 0)
+}
@@ -0,0 +1,78 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use toml::{Table, Value};
+
+use super::TOML;
+use crate::exit_err;
+
+/// Replaces each `key.workspace = true` in `package` with the value `[workspace.package]` of the workspace root
+/// declares, the way cargo resolves a member's inherited keys
+pub(super) fn inherit(project_dir: &Path, manifest: &Table, package: &mut Table) -> io::Result<()> {
+    let inherited = package
+        .iter()
+        .filter(|(_, value)| {
+            value
+                .get("workspace")
+                .and_then(Value::as_bool)
+                .unwrap_or_default()
+        })
+        .map(|(key, _)| key.clone())
+        .collect::<Vec<_>>();
+    let Some(first) = inherited.first() else {
+        return Ok(());
+    };
+
+    let (root_path, root) = match manifest.contains_key("workspace") {
+        true => (project_dir.join(TOML), manifest.clone()),
+        false => find_root(project_dir, package)?.unwrap_or_else(|| {
+            exit_err!(
+                "'{first}.workspace = true' in {TOML} but no workspace root was found above: '{}'",
+                project_dir.display()
+            )
+        }),
+    };
+
+    let workspace_package = root
+        .get("workspace")
+        .and_then(|workspace| workspace.get("package"))
+        .and_then(Value::as_table);
+    for key in inherited {
+        let Some(value) = workspace_package.and_then(|table| table.get(&key)) else {
+            exit_err!(
+                "'{key}' is inherited from the workspace but '[workspace.package]' does not define it in: '{}'",
+                root_path.display()
+            )
+        };
+        package.insert(key, value.clone());
+    }
+    Ok(())
+}
+
+/// Locates the workspace root from `package.workspace` or the nearest parent manifest with a `[workspace]` table
+fn find_root(project_dir: &Path, package: &Table) -> io::Result<Option<(PathBuf, Table)>> {
+    if let Some(root) = package.get("workspace").and_then(Value::as_str) {
+        let path = project_dir.join(root).join(TOML);
+        return read(&path).map(|manifest| Some((path, manifest)));
+    }
+
+    for dir in project_dir.canonicalize()?.ancestors().skip(1) {
+        let path = dir.join(TOML);
+        if !path.is_file() {
+            continue;
+        }
+        let manifest = read(&path)?;
+        if manifest.contains_key("workspace") {
+            return Ok(Some((path, manifest)));
+        }
+    }
+    Ok(None)
+}
+
+fn read(path: &Path) -> io::Result<Table> {
+    Ok(fs::read_to_string(path)?
+        .parse::<Table>()
+        .unwrap_or_else(|err| exit_err!("failed to parse: '{}': {err}", path.display())))
+}
@@ -1,8 +1,16 @@
 mod args;
 mod forge;
 use args::Args;
-use forge::Forge;
+use forge::{Forge, Run};
 
-fn main() -> std::io::Result<()> {
-    Forge::from(Args::parse())?.forge()
+fn main() {
+    let forge = match Forge::from(Args::parse()) {
+        Ok(Run::Stage(forge)) => *forge,
+        Ok(Run::Exit(code)) => forge::warnings::exit(code),
+        Err(err) => err.exit(),
+    };
+    if let Err(err) = forge.forge() {
+        err.exit()
+    }
+    forge::warnings::render()
 }